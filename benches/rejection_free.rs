@@ -0,0 +1,56 @@
+//! Compare the rejection-free chain with the ordinary one by the CPU time per integrated
+//! autocorrelation time of the slice length standard deviation, which accounts for both the
+//! speed of a sweep and how much a sweep decorrelates.
+//!
+//! Usage: cargo bench --bench rejection_free -- [timespan] [length] [move ratio] [sweeps]
+//! with by default 20 slices of length 100 at move ratio 0.4, timing chains of 1000 sweeps
+//! after 1000 sweeps of equilibration.
+use monte_carlo_cdt::monitor::chain_cost;
+use monte_carlo_cdt::moves::Moves;
+use monte_carlo_cdt::universe::Universe;
+
+const EQ_SWEEPS: usize = 1000;
+
+fn main() {
+    // cargo passes --bench to benchmarks without the default harness
+    let arguments: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|argument| !argument.starts_with("--"))
+        .collect();
+    let argument = |index: usize, default: &str| {
+        arguments
+            .get(index)
+            .map_or(default, String::as_str)
+            .parse::<f64>()
+            .expect("the arguments should be numbers")
+    };
+    let timespan = argument(0, "20") as usize;
+    let length = argument(1, "100") as usize;
+    let move_ratio = argument(2, "0.4") as f32;
+    let sweeps = argument(3, "1000") as usize;
+
+    let mut universe = Universe::new(timespan, length);
+    let mut moves = Moves::from_ratio(move_ratio, &[]);
+    for _ in 0..EQ_SWEEPS * universe.size() {
+        universe.mcmc_step(&mut moves);
+    }
+
+    println!("chain, autocorrelation time, seconds per sweep, seconds per autocorrelation time");
+    let mut costs = Vec::new();
+    for (chain, rejection_free) in [("ordinary", false), ("rejection-free", true)] {
+        let mut moves = Moves::from_ratio(move_ratio, &[]);
+        let (tau, seconds) = chain_cost(&universe, &mut moves, sweeps, rejection_free);
+        println!(
+            "{}, {:.3}, {:.3e}, {:.3e}",
+            chain,
+            tau,
+            seconds,
+            tau * seconds
+        );
+        costs.push(tau * seconds);
+    }
+    println!(
+        "speedup per autocorrelation time: {:.2}",
+        costs[0] / costs[1]
+    );
+}
@@ -38,8 +38,12 @@ for parameter_set in parameters:
     if T > 200:
         Ls.append(L)
         Ts.append(T)
-        datafile = datapath + parameter_set["name"] + ".csv"
-        lengths = np.loadtxt(datafile, delimiter=',', dtype=int, usecols=range(0, T))
+        if parameter_set.get("output_format") == "npy":
+            datafile = datapath + parameter_set["name"] + ".npy"
+            lengths = np.load(datafile, mmap_mode='r')
+        else:
+            datafile = datapath + parameter_set["name"] + ".csv"
+            lengths = np.loadtxt(datafile, delimiter=',', dtype=int, usecols=range(0, T))
         stds.append(np.std(lengths, axis=1))
         covs.append(covariance(lengths))
 
@@ -0,0 +1,86 @@
+//! Compare the slice length histogram of the direct sampler with that of a long Markov chain,
+//! which should agree within statistical error as both sample uniform triangulations.
+//!
+//! Usage: cargo run --release --example direct_sampling_check [timespan] [volume] [samples]
+//! with by default 3 slices of total length 12 and 100000 samples of either, split into 20
+//! batches whose spread gives the standard errors. The Markov chain takes one sample per sweep,
+//! and the process exits with failure if any length is off by more than five standard errors.
+use monte_carlo_cdt::direct::DirectSampler;
+use monte_carlo_cdt::moves::Moves;
+use monte_carlo_cdt::universe::Universe;
+
+const BATCHES: usize = 20;
+const EQ_SWEEPS: usize = 1000;
+const MAX_DEVIATION: f64 = 5.0;
+
+fn main() {
+    let arguments: Vec<usize> = std::env::args()
+        .skip(1)
+        .map(|argument| argument.parse().expect("the arguments should be numbers"))
+        .collect();
+    let timespan = arguments.first().copied().unwrap_or(3);
+    let volume = arguments.get(1).copied().unwrap_or(12);
+    let samples = arguments.get(2).copied().unwrap_or(100000);
+    let per_batch = (samples / BATCHES).max(1);
+    let sampler = DirectSampler::new(timespan, volume).unwrap_or_else(|err| panic!("{}", err));
+    let max_length = volume - (timespan - 1);
+
+    // the same volume in every slice as far as possible
+    let lengths: Vec<usize> = (0..timespan)
+        .map(|t| volume / timespan + usize::from(t < volume % timespan))
+        .collect();
+    let mut universe = Universe::from_profile(&lengths).expect("the volume is too small");
+    let mut moves = Moves::from_ratio(0.4, &[]);
+    let sweep = universe.size();
+    for _ in 0..EQ_SWEEPS * sweep {
+        universe.mcmc_step(&mut moves);
+    }
+
+    let mut direct = vec![vec![0.0; max_length + 1]; BATCHES];
+    let mut chain = vec![vec![0.0; max_length + 1]; BATCHES];
+    for batch in 0..BATCHES {
+        for _ in 0..per_batch {
+            for &length in sampler.sample().length_profile(0).lengths() {
+                direct[batch][length] += 1.0 / (per_batch * timespan) as f64;
+            }
+            for _ in 0..sweep {
+                universe.mcmc_step(&mut moves);
+            }
+            for &length in universe.length_profile(0).lengths() {
+                chain[batch][length] += 1.0 / (per_batch * timespan) as f64;
+            }
+        }
+    }
+
+    // the mean and standard error of the fraction of slices of every length over the batches
+    let estimate = |histograms: &[Vec<f64>], length: usize| {
+        let mean = histograms.iter().map(|h| h[length]).sum::<f64>() / BATCHES as f64;
+        let variance = histograms
+            .iter()
+            .map(|h| (h[length] - mean).powi(2))
+            .sum::<f64>()
+            / (BATCHES - 1) as f64;
+        (mean, (variance / BATCHES as f64).sqrt())
+    };
+
+    println!("length, direct, error, chain, error, deviation");
+    let mut worst = 0.0f64;
+    for length in 1..=max_length {
+        let (direct_mean, direct_error) = estimate(&direct, length);
+        let (chain_mean, chain_error) = estimate(&chain, length);
+        let error = direct_error.hypot(chain_error);
+        let deviation = match direct_mean - chain_mean {
+            0.0 => 0.0,
+            difference => difference / error,
+        };
+        worst = worst.max(deviation.abs());
+        println!(
+            "{}, {:.5}, {:.5}, {:.5}, {:.5}, {:.2}",
+            length, direct_mean, direct_error, chain_mean, chain_error, deviation
+        );
+    }
+    println!("largest deviation: {:.2} standard errors", worst);
+    if worst > MAX_DEVIATION {
+        std::process::exit(1);
+    }
+}
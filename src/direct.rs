@@ -0,0 +1,182 @@
+use crate::universe::Universe;
+
+/// The largest number of table entries the sampler builds, taking 2 GiB of memory
+const MAX_ENTRIES: usize = 1 << 28;
+
+/// Exact sampler of uniformly random triangulations of the torus with a given number of slices
+/// and triangles, independent of each other and of the Markov chain.
+///
+/// Seen from a vertex of slice 0, a triangulation is a word of up and down triangles for every
+/// slab, starting with the up triangle on that vertex, together with the twist of the gluing.
+/// A slab between slices of lengths l and l' has binomial(l + l' - 1, l') such words and slice 0
+/// has l_0 possible twists, so the length profile is drawn with weight
+/// l_0 prod_t binomial(l_t + l_{t+1} - 1, l_{t+1}), after which the words and twist are uniform.
+/// The profile is drawn by dynamic programming over the slices and the remaining volume, with a
+/// table for every length of slice 0. For T slices and volume V, building these takes O(T V^4)
+/// time and keeping them O(T V^3) memory, so this is meant for small universes, such as to check
+/// the distribution the Markov chain converges to, and larger tables than MAX_ENTRIES are refused.
+pub struct DirectSampler {
+    timespan: usize,
+    volume: usize,
+    max_length: usize,
+    /// binomial(l + l' - 1, l') 2^-(l + l') for slice lengths l and l', the factor of two
+    /// keeping the numbers finite while being the same for every profile
+    transfer: Vec<f64>,
+    /// the logarithm of the total weight of all profiles with a given length of slice 0
+    log_weights: Vec<f64>,
+    /// the completion tables (see completions) for every length of slice 0
+    tables: Vec<Vec<Vec<f64>>>,
+}
+
+impl DirectSampler {
+    /// The sampler of triangulations with the given number of slices and total slice length
+    pub fn new(timespan: usize, volume: usize) -> Result<Self, String> {
+        if timespan == 0 || volume < timespan {
+            return Err(
+                "the universe needs at least one slice, and every slice at least length one"
+                    .to_string(),
+            );
+        }
+        let max_length = volume - (timespan - 1);
+
+        // a table of (max_length + 1) x (volume + 1) for every slice and every length of slice 0
+        let entries = [max_length, timespan, max_length + 1, volume + 1]
+            .iter()
+            .try_fold(1usize, |entries, &factor| entries.checked_mul(factor))
+            .unwrap_or(usize::MAX);
+        if entries > MAX_ENTRIES {
+            return Err(format!(
+                "the direct sampler would need {:.1} GiB of tables for {} slices of total length {}, \
+                 more than the {} GiB allowed",
+                entries as f64 * 8.0 / (1u64 << 30) as f64,
+                timespan,
+                volume,
+                (MAX_ENTRIES * 8) >> 30
+            ));
+        }
+
+        let mut log_factorials = vec![0.0; 2 * max_length + 1];
+        for n in 1..log_factorials.len() {
+            log_factorials[n] = log_factorials[n - 1] + (n as f64).ln();
+        }
+        let size = max_length + 1;
+        let mut transfer = vec![0.0; size * size];
+        for l in 1..size {
+            for l_next in 1..size {
+                let log_binomial =
+                    log_factorials[l + l_next - 1] - log_factorials[l - 1] - log_factorials[l_next];
+                transfer[l * size + l_next] =
+                    (log_binomial - (l + l_next) as f64 * std::f64::consts::LN_2).exp();
+            }
+        }
+
+        let mut sampler = DirectSampler {
+            timespan,
+            volume,
+            max_length,
+            transfer,
+            log_weights: vec![f64::NEG_INFINITY; size],
+            tables: vec![Vec::new(); size],
+        };
+        for first in 1..size {
+            let (tables, log_scales) = sampler.completions(first);
+            let weight = tables[0][first * (volume + 1) + volume - first];
+            sampler.log_weights[first] = (first as f64).ln() + weight.ln() + log_scales[0];
+            sampler.tables[first] = tables;
+        }
+        Ok(sampler)
+    }
+
+    /// Draw an independent triangulation
+    pub fn sample(&self) -> Universe {
+        let size = self.max_length + 1;
+        let columns = self.volume + 1;
+
+        // the length of slice 0, and then every next slice given the volume left for the rest
+        let largest = self
+            .log_weights
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = self
+            .log_weights
+            .iter()
+            .map(|log_weight| (log_weight - largest).exp())
+            .collect();
+        let first = choose(&weights);
+        let tables = &self.tables[first];
+
+        let mut lengths = vec![first];
+        let mut remaining = self.volume - first;
+        for t in 1..self.timespan {
+            let previous = lengths[t - 1];
+            let weights: Vec<f64> = (0..=remaining.min(self.max_length))
+                .map(|l| match l {
+                    0 => 0.0,
+                    _ => {
+                        self.transfer[previous * size + l] * tables[t][l * columns + remaining - l]
+                    }
+                })
+                .collect();
+            let length = choose(&weights);
+            lengths.push(length);
+            remaining -= length;
+        }
+
+        // uniformly random slabs and twist
+        Universe::shuffled(&lengths).expect("Somehow the sampled slice lengths are invalid")
+    }
+
+    /// The total weight of completing a profile from slice t with length l and volume r left for
+    /// slices t + 1 up to the last one, which is glued to slice 0 of the given length; stored
+    /// as [t][l * (volume + 1) + r], every table being rescaled by the exponent of its log scale
+    fn completions(&self, first: usize) -> (Vec<Vec<f64>>, Vec<f64>) {
+        let size = self.max_length + 1;
+        let columns = self.volume + 1;
+        let mut tables = vec![vec![0.0; size * columns]; self.timespan];
+        let mut log_scales = vec![0.0; self.timespan];
+
+        for l in 1..size {
+            tables[self.timespan - 1][l * columns] = self.transfer[l * size + first];
+        }
+        for t in (0..self.timespan - 1).rev() {
+            let (current, next) = tables.split_at_mut(t + 1);
+            let (current, next) = (&mut current[t], &next[0]);
+            let mut largest = 0.0f64;
+            for l in 1..size {
+                for r in 1..columns {
+                    let weight = (1..=r.min(self.max_length))
+                        .map(|l_next| {
+                            self.transfer[l * size + l_next] * next[l_next * columns + r - l_next]
+                        })
+                        .sum::<f64>();
+                    current[l * columns + r] = weight;
+                    largest = largest.max(weight);
+                }
+            }
+            if largest > 0.0 {
+                current.iter_mut().for_each(|weight| *weight /= largest);
+                log_scales[t] = log_scales[t + 1] + largest.ln();
+            } else {
+                log_scales[t] = log_scales[t + 1];
+            }
+        }
+        (tables, log_scales)
+    }
+}
+
+/// Choose an index with probability proportional to its weight
+fn choose(weights: &[f64]) -> usize {
+    let total: f64 = weights.iter().sum();
+    let mut choice = fastrand::f64() * total;
+    for (index, &weight) in weights.iter().enumerate() {
+        choice -= weight;
+        if choice < 0.0 && weight > 0.0 {
+            return index;
+        }
+    }
+    weights
+        .iter()
+        .rposition(|&weight| weight > 0.0)
+        .expect("Somehow there is nothing to choose from")
+}
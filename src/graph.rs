@@ -0,0 +1,209 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// A graph derived from a triangulation, with every link stored exactly once
+#[derive(Clone, Debug)]
+pub struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub kind: &'static str,
+    pub time: usize,
+    pub position: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Edge {
+    pub source: usize,
+    pub target: usize,
+    pub kind: &'static str,
+}
+
+/// Which graph of the triangulation to export
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub enum GraphKind {
+    Dual,
+    Vertex,
+}
+
+/// The file format to export a graph in
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub enum GraphFormat {
+    GraphMl,
+    Dot,
+    EdgeList,
+}
+
+impl Graph {
+    pub fn new(nodes: Vec<Node>, edges: Vec<Edge>) -> Self {
+        Graph { nodes, edges }
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// The neighbours of every node, ignoring the direction and type of the edges
+    pub fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); self.nodes.len()];
+        for edge in &self.edges {
+            adjacency[edge.source].push(edge.target);
+            adjacency[edge.target].push(edge.source);
+        }
+        adjacency
+    }
+
+    pub fn write<W: Write>(&self, output: &mut W, format: GraphFormat) -> io::Result<()> {
+        match format {
+            GraphFormat::GraphMl => self.write_graphml(output),
+            GraphFormat::Dot => self.write_dot(output),
+            GraphFormat::EdgeList => self.write_edge_list(output),
+        }
+    }
+
+    fn write_graphml<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            output,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            output,
+            r#"  <key id="kind" for="node" attr.name="kind" attr.type="string"/>"#
+        )?;
+        writeln!(
+            output,
+            r#"  <key id="time" for="node" attr.name="time" attr.type="int"/>"#
+        )?;
+        writeln!(
+            output,
+            r#"  <key id="position" for="node" attr.name="position" attr.type="int"/>"#
+        )?;
+        writeln!(
+            output,
+            r#"  <key id="type" for="edge" attr.name="type" attr.type="string"/>"#
+        )?;
+        writeln!(output, r#"  <graph id="cdt" edgedefault="undirected">"#)?;
+        for (id, node) in self.nodes.iter().enumerate() {
+            writeln!(
+                output,
+                r#"    <node id="{}"><data key="kind">{}</data><data key="time">{}</data><data key="position">{}</data></node>"#,
+                id, node.kind, node.time, node.position
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                output,
+                r#"    <edge source="{}" target="{}"><data key="type">{}</data></edge>"#,
+                edge.source, edge.target, edge.kind
+            )?;
+        }
+        writeln!(output, "  </graph>")?;
+        writeln!(output, "</graphml>")
+    }
+
+    fn write_dot<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        writeln!(output, "graph cdt {{")?;
+        for (id, node) in self.nodes.iter().enumerate() {
+            writeln!(
+                output,
+                "    {} [kind={}, time={}, position={}];",
+                id, node.kind, node.time, node.position
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                output,
+                "    {} -- {} [type={}];",
+                edge.source, edge.target, edge.kind
+            )?;
+        }
+        writeln!(output, "}}")
+    }
+
+    fn write_edge_list<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        for edge in &self.edges {
+            writeln!(output, "{} {} {}", edge.source, edge.target, edge.kind)?;
+        }
+        Ok(())
+    }
+}
+
+/// Graph distances from the source to all nodes up to the maximum distance (breadth-first search),
+/// nodes further away are at distance usize::MAX
+pub fn distances(adjacency: &[Vec<usize>], source: usize, max_distance: usize) -> Vec<usize> {
+    let mut distances = vec![usize::MAX; adjacency.len()];
+    distances[source] = 0;
+    let mut front = vec![source];
+    for distance in 1..=max_distance {
+        let mut next = Vec::new();
+        for &node in &front {
+            for &nbr in &adjacency[node] {
+                if distances[nbr] == usize::MAX {
+                    distances[nbr] = distance;
+                    next.push(nbr);
+                }
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        front = next;
+    }
+    distances
+}
+
+impl GraphKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GraphKind::Dual => "dual",
+            GraphKind::Vertex => "vertex",
+        }
+    }
+}
+
+impl GraphFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::GraphMl => "graphml",
+            GraphFormat::Dot => "dot",
+            GraphFormat::EdgeList => "edgelist",
+        }
+    }
+}
+
+impl FromStr for GraphKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dual" => Ok(GraphKind::Dual),
+            "vertex" => Ok(GraphKind::Vertex),
+            _ => Err(format!(
+                "unknown graph '{}', expected 'dual' or 'vertex'",
+                s
+            )),
+        }
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "dot" => Ok(GraphFormat::Dot),
+            "edgelist" => Ok(GraphFormat::EdgeList),
+            _ => Err(format!(
+                "unknown graph format '{}', expected 'graphml', 'dot' or 'edgelist'",
+                s
+            )),
+        }
+    }
+}
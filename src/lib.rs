@@ -0,0 +1,10 @@
+pub mod direct;
+pub mod graph;
+pub mod linalg;
+pub mod monitor;
+pub mod moves;
+pub mod observables;
+pub mod output;
+pub mod state;
+pub mod statistics;
+pub mod universe;
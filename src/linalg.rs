@@ -0,0 +1,218 @@
+/// Invert a symmetric positive definite matrix (row-major, n by n) using its Cholesky decomposition,
+/// returns None if the matrix turns out not to be positive definite
+pub fn cholesky_inverse(matrix: &[f64], n: usize) -> Option<Vec<f64>> {
+    // decompose matrix = L L^T, with L lower triangular
+    let mut lower = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..=i {
+            let sum = matrix[i * n + j]
+                - (0..j)
+                    .map(|k| lower[i * n + k] * lower[j * n + k])
+                    .sum::<f64>();
+            if i == j {
+                if sum <= 0.0 {
+                    return None;
+                }
+                lower[i * n + i] = sum.sqrt();
+            } else {
+                lower[i * n + j] = sum / lower[j * n + j];
+            }
+        }
+    }
+
+    // invert L by forward substitution
+    let mut lower_inverse = vec![0.0; n * n];
+    for j in 0..n {
+        lower_inverse[j * n + j] = 1.0 / lower[j * n + j];
+        for i in j + 1..n {
+            let sum = (j..i)
+                .map(|k| lower[i * n + k] * lower_inverse[k * n + j])
+                .sum::<f64>();
+            lower_inverse[i * n + j] = -sum / lower[i * n + i];
+        }
+    }
+
+    // matrix^-1 = L^-T L^-1
+    let mut inverse = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..=i {
+            let value = (i..n)
+                .map(|k| lower_inverse[k * n + i] * lower_inverse[k * n + j])
+                .sum::<f64>();
+            inverse[i * n + j] = value;
+            inverse[j * n + i] = value;
+        }
+    }
+    Some(inverse)
+}
+
+/// All eigenvalues of a symmetric matrix (row-major, n by n) in descending order,
+/// computed with cyclic Jacobi rotations
+pub fn symmetric_eigenvalues(matrix: &[f64], n: usize) -> Vec<f64> {
+    let mut a = matrix.to_vec();
+    let norm = a.iter().map(|x| x * x).sum::<f64>().sqrt();
+    for _ in 0..100 {
+        let off_diagonal = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i * n + j] * a[i * n + j])
+            .sum::<f64>()
+            .sqrt();
+        if off_diagonal <= 1e-12 * norm {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p * n + q].abs() <= f64::MIN_POSITIVE {
+                    continue;
+                }
+                // rotate such that the (p, q) element vanishes
+                let theta = (a[q * n + q] - a[p * n + p]) / (2.0 * a[p * n + q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[k * n + p], a[k * n + q]);
+                    a[k * n + p] = c * akp - s * akq;
+                    a[k * n + q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p * n + k], a[q * n + k]);
+                    a[p * n + k] = c * apk - s * aqk;
+                    a[q * n + k] = s * apk + c * aqk;
+                }
+            }
+        }
+    }
+    let mut eigenvalues: Vec<f64> = (0..n).map(|i| a[i * n + i]).collect();
+    eigenvalues.sort_by(|x, y| y.total_cmp(x));
+    eigenvalues
+}
+
+/// The largest eigenvalues of a symmetric matrix, given only through its product with a vector,
+/// in descending order, computed with the Lanczos iteration with full reorthogonalization
+/// from the given start vector
+pub fn lanczos_largest<F>(multiply: F, start: &[f64], iterations: usize, count: usize) -> Vec<f64>
+where
+    F: Fn(&[f64], &mut [f64]),
+{
+    let n = start.len();
+    let norm = dot(start, start).sqrt();
+    let mut vector: Vec<f64> = start.iter().map(|x| x / norm).collect();
+
+    // build an orthonormal basis of the Krylov space of the start vector,
+    // in which the matrix is tridiagonal
+    let mut basis: Vec<Vec<f64>> = Vec::with_capacity(iterations.min(n));
+    let mut diagonal = Vec::with_capacity(iterations.min(n));
+    let mut off_diagonal = Vec::with_capacity(iterations.min(n));
+    let mut product = vec![0.0; n];
+    loop {
+        multiply(&vector, &mut product);
+        diagonal.push(dot(&product, &vector));
+        basis.push(vector);
+
+        // orthogonalize against the whole basis, twice to keep it orthogonal in finite precision
+        for _ in 0..2 {
+            for previous in &basis {
+                let overlap = dot(&product, previous);
+                for (x, y) in product.iter_mut().zip(previous) {
+                    *x -= overlap * y;
+                }
+            }
+        }
+        let norm = dot(&product, &product).sqrt();
+        if basis.len() == iterations.min(n) || norm < 1e-10 {
+            break;
+        }
+        off_diagonal.push(norm);
+        vector = product.iter().map(|x| x / norm).collect();
+    }
+
+    let size = diagonal.len();
+    (0..count.min(size))
+        .map(|k| tridiagonal_eigenvalue(&diagonal, &off_diagonal, size - 1 - k))
+        .collect()
+}
+
+/// Solve matrix x = rhs for a symmetric positive (semi-)definite matrix, given only through its
+/// product with a vector, with the conjugate gradient method up to the relative tolerance
+pub fn conjugate_gradient<F>(multiply: F, rhs: &[f64], tolerance: f64) -> Vec<f64>
+where
+    F: Fn(&[f64], &mut [f64]),
+{
+    let n = rhs.len();
+    let mut solution = vec![0.0; n];
+    let mut residual = rhs.to_vec();
+    let mut direction = rhs.to_vec();
+    let mut product = vec![0.0; n];
+    let target = tolerance * tolerance * dot(rhs, rhs);
+    let mut residual_norm = dot(&residual, &residual);
+    for _ in 0..10 * n {
+        if residual_norm <= target {
+            break;
+        }
+        multiply(&direction, &mut product);
+        let step = residual_norm / dot(&direction, &product);
+        for i in 0..n {
+            solution[i] += step * direction[i];
+            residual[i] -= step * product[i];
+        }
+        let previous_norm = residual_norm;
+        residual_norm = dot(&residual, &residual);
+        for i in 0..n {
+            direction[i] = residual[i] + residual_norm / previous_norm * direction[i];
+        }
+    }
+    solution
+}
+
+fn dot(x: &[f64], y: &[f64]) -> f64 {
+    x.iter().zip(y).map(|(a, b)| a * b).sum()
+}
+
+/// The k-th lowest eigenvalue of a symmetric tridiagonal matrix,
+/// found by bisection on the Sturm sequence count
+fn tridiagonal_eigenvalue(diagonal: &[f64], off_diagonal: &[f64], k: usize) -> f64 {
+    // the number of eigenvalues below x
+    let count_below = |x: f64| {
+        let mut count = 0;
+        let mut q = 1.0;
+        for i in 0..diagonal.len() {
+            let coupling = if i == 0 { 0.0 } else { off_diagonal[i - 1] };
+            q = diagonal[i] - x - coupling * coupling / q;
+            if q == 0.0 {
+                q = f64::EPSILON;
+            }
+            if q < 0.0 {
+                count += 1;
+            }
+        }
+        count
+    };
+
+    // all eigenvalues lie within the Gershgorin discs
+    let radius = |i: usize| {
+        let left = if i == 0 {
+            0.0
+        } else {
+            off_diagonal[i - 1].abs()
+        };
+        let right = off_diagonal.get(i).map_or(0.0, |x| x.abs());
+        left + right
+    };
+    let mut lower = (0..diagonal.len())
+        .map(|i| diagonal[i] - radius(i))
+        .fold(f64::INFINITY, f64::min);
+    let mut upper = (0..diagonal.len())
+        .map(|i| diagonal[i] + radius(i))
+        .fold(f64::NEG_INFINITY, f64::max);
+    while upper - lower > 1e-12 * (upper.abs() + lower.abs()).max(1.0) {
+        let middle = 0.5 * (lower + upper);
+        if count_below(middle) > k {
+            upper = middle;
+        } else {
+            lower = middle;
+        }
+    }
+    0.5 * (lower + upper)
+}
@@ -1,22 +1,29 @@
-mod universe;
+use monte_carlo_cdt::direct::DirectSampler;
+use monte_carlo_cdt::graph::{GraphFormat, GraphKind};
+use monte_carlo_cdt::monitor::{chain_cost, Monitor};
+use monte_carlo_cdt::moves::{MoveKind, MoveWeight, Moves};
+use monte_carlo_cdt::observables::{self, Alignment, ObservableKind};
+use monte_carlo_cdt::output::{self, OutputFormat};
+use monte_carlo_cdt::{state, universe};
 use serde_json::json;
 use std::fs::File;
 use std::io::BufWriter;
 use std::io::Write;
-use std::time::SystemTime;
+use std::time::{Instant, SystemTime};
 use structopt::StructOpt;
+use universe::{Embedding, LengthDistribution, Start, Statistic};
 
 /// A Markov Chain Monte Carlo simulation of 2-dimensional Causal Dynamical Triangulations.
 #[derive(StructOpt, Debug, serde::Serialize)]
 #[structopt(name = "monte_carlo_CDT")]
 struct Opt {
     /// Number of timeslices
-    #[structopt(short = "t", long)]
-    timespan: usize,
+    #[structopt(short = "t", long, required_unless_one = &["initial-state", "profile"])]
+    timespan: Option<usize>,
 
     /// Average number of links per timeslice
-    #[structopt(short = "l", long)]
-    length: usize,
+    #[structopt(short = "l", long, required_unless_one = &["initial-state", "profile"])]
+    length: Option<usize>,
 
     /// Number of Markov Chain timesteps to save
     #[structopt(short = "n", long)]
@@ -40,48 +47,232 @@ struct Opt {
     #[structopt(short = "s", long)]
     output_std: bool,
 
+    /// Statistic of the slice lengths to output instead of the length profile ("mean", "variance",
+    /// "stdev", "skewness", "kurtosis", "min", "max" or "binder"), can be repeated
+    #[structopt(long)]
+    statistic: Vec<Statistic>,
+
+    /// File format of the measurements ("csv", "npy" or "jsonl")
+    #[structopt(short = "f", long, default_value = "csv")]
+    output_format: OutputFormat,
+
     /// Probability of performing a shard move for a single Markov chain step
     /// in the equilibration phase
     #[structopt(short = "e", long, default_value = "0.5")]
     // TODO: adjust default value when we know optimal value
     move_ratio_eq: f32,
 
+    /// Weight of a move relative to the others, given as "name=weight" ("flip", "shard",
+    /// "heatbath" or "cluster"), overriding the move ratios, can be repeated
+    #[structopt(long = "move")]
+    move_weights: Vec<MoveWeight>,
+
+    /// Option to choose the move ratio of the measurement phase by comparing short chains at
+    /// several move ratios after equilibration, picking the one with the shortest
+    /// autocorrelation time of the slice length standard deviation per CPU second; not possible
+    /// when the flip or shard weight is given by --move
+    #[structopt(long)]
+    tune_move_ratio: bool,
+
+    /// Move ratios compared when tuning the move ratio
+    #[structopt(
+        long,
+        default_value = "0.1,0.2,0.3,0.4,0.5,0.6,0.7,0.8,0.9",
+        use_delimiter = true
+    )]
+    tuning_ratios: Vec<f32>,
+
+    /// Length of the chain at every move ratio in sweeps when tuning the move ratio
+    #[structopt(long, default_value = "100")]
+    tuning_sweeps: usize,
+
     /// Length of equilibration phase in sweeps (bake-in)
     #[structopt(short = "b", long, default_value = "0")]
     eq_sweeps: usize,
 
+    /// Option to extend the equilibration phase beyond the given length until the slice length
+    /// standard deviation and maximum are stationary
+    #[structopt(long)]
+    auto_equilibrate: bool,
+
+    /// Maximal length of the equilibration phase in sweeps when equilibrating automatically
+    #[structopt(long, default_value = "10000")]
+    max_eq_sweeps: usize,
+
+    /// Option to draw every measurement independently with the exact direct sampler instead of
+    /// running the Markov chain, skipping the equilibration phase; only feasible for small
+    /// universes, as the tables of the sampler grow quickly with the volume and are refused beyond
+    /// 2 GiB
+    #[structopt(long)]
+    direct_sampling: bool,
+
+    /// Option to skip rejected flips by sampling flippable pairs directly (n-fold way), which
+    /// gives the same Markov chain per step but is faster when few triangles can be flipped
+    #[structopt(long)]
+    rejection_free: bool,
+
     /// Number of sweeps inbetween measurements (pause)
     #[structopt(short = "p", long, default_value = "1.0")]
     pause: f32,
 
+    /// Option to instead set the pause to this multiple of the integrated autocorrelation time of
+    /// the slice length standard deviation and maximum, estimated in a pilot phase
+    #[structopt(long)]
+    auto_pause: Option<f32>,
+
+    /// Length of the pilot phase in sweeps when setting the pause automatically
+    #[structopt(long, default_value = "100")]
+    pilot_sweeps: usize,
+
     #[structopt(short = "o", long)]
     output_folder: String,
+
+    /// Graph of the final triangulation to export ("dual" or "vertex"), can be repeated
+    #[structopt(short = "g", long)]
+    graph: Vec<GraphKind>,
+
+    /// File format of the exported graphs ("graphml", "dot" or "edgelist")
+    #[structopt(long, default_value = "graphml")]
+    graph_format: GraphFormat,
+
+    /// Triangulation to start from instead of the flat universe, either a slab orientation
+    /// file or a dual graph exported as GraphML
+    #[structopt(short = "i", long)]
+    initial_state: Option<String>,
+
+    /// Triangulation to start from when no initial state is given ("flat", "random" or
+    /// "pinched"), all with the volume of the flat universe
+    #[structopt(long, default_value = "flat")]
+    start: Start,
+
+    /// Distribution of the slice lengths of a random start ("multinomial" or "uniform")
+    #[structopt(long, default_value = "multinomial")]
+    length_distribution: LengthDistribution,
+
+    /// Slice lengths to start from instead, with evenly spread up and down triangles
+    #[structopt(long, use_delimiter = true, conflicts_with = "initial-state")]
+    profile: Vec<usize>,
+
+    /// Option to save the final triangulation as a slab orientation file
+    #[structopt(long)]
+    save_state: bool,
+
+    /// Additional observable to measure ("coordination", "correlator", "effective_action",
+    /// "transfer_matrix", "light_cone", "ricci", "loops", "laplacian", "aligned_profile" or
+    /// "histogram"), can be repeated
+    #[structopt(long)]
+    observable: Vec<ObservableKind>,
+
+    /// Number of batches the measurements are split into for error estimation
+    #[structopt(long, default_value = "10")]
+    batch_count: usize,
+
+    /// Regularization of the covariance matrix inversion, relative to the average variance
+    #[structopt(long, default_value = "1e-6")]
+    regularization: f64,
+
+    /// Largest sphere radius for the quantum Ricci curvature
+    #[structopt(long, default_value = "10")]
+    max_distance: usize,
+
+    /// Number of lowest eigenvalues of the graph Laplacians to compute
+    #[structopt(long, default_value = "10")]
+    eigenvalues: usize,
+
+    /// Feature of the length profile kept at the middle slice for the aligned average profile
+    /// ("centre" of volume or "maximum")
+    #[structopt(long, default_value = "centre")]
+    alignment: Alignment,
+
+    /// Surface to embed the mesh in ("torus", "cylinder" or "strip")
+    #[structopt(long, default_value = "torus")]
+    embedding: Embedding,
+
+    /// Number of sweeps inbetween mesh snapshots (and graphs), none are written if zero
+    #[structopt(long, default_value = "0")]
+    snapshot_interval: usize,
 }
 
 // example commands (on Windows):
 // cargo build --release
 // target\release\monte-carlo-cdt.exe -t 10 -l 100 -n 100 -r 0.5
 fn main() {
-    let _ = measurement();
+    if let Err(err) = measurement() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 }
 
 fn measurement() -> std::io::Result<()> {
     // set parameters
     let opt = Opt::from_args();
-    let timespan = opt.timespan;
-    let length = opt.length;
     let n_save = opt.n_save;
-    let move_ratio = opt.move_ratio;
+    let mut move_ratio = opt.move_ratio;
     let is_measurement = opt.is_measurement;
     let move_ratio_eq = opt.move_ratio_eq;
+    let move_weights = opt.move_weights;
+    let tune_move_ratio = opt.tune_move_ratio;
+    let tuning_ratios = opt.tuning_ratios;
+    let tuning_sweeps = opt.tuning_sweeps;
     let eq_sweeps = opt.eq_sweeps;
+    let auto_equilibrate = opt.auto_equilibrate;
+    let max_eq_sweeps = opt.max_eq_sweeps;
+    let auto_pause = opt.auto_pause;
+    let pilot_sweeps = opt.pilot_sweeps;
+    let direct_sampling = opt.direct_sampling;
+    let rejection_free = opt.rejection_free;
     let output_folder = opt.output_folder;
     let visualisation = opt.visualisation;
     let output_std = opt.output_std;
+    let mut statistics = opt.statistic;
+    if output_std && !statistics.contains(&Statistic::Stdev) {
+        statistics.insert(0, Statistic::Stdev);
+    }
+    let output_format = opt.output_format;
+    let graphs = opt.graph;
+    let graph_format = opt.graph_format;
+    let initial_state = opt.initial_state;
+    let start_condition = opt.start;
+    let length_distribution = opt.length_distribution;
+    let profile = opt.profile;
+    let save_state = opt.save_state;
+    let snapshot_interval = opt.snapshot_interval;
+    let embedding = opt.embedding;
+    let observable_kinds = opt.observable;
+    let batch_count = opt.batch_count;
+    let regularization = opt.regularization;
+    let max_distance = opt.max_distance;
+    let eigenvalues = opt.eigenvalues;
+    let alignment = opt.alignment;
 
-    let sweep = 2 * timespan * length;
+    // big bang, or continue from a given triangulation or length profile
+    let mut universe = match (&initial_state, profile.is_empty(), start_condition) {
+        (Some(path), _, _) => state::read_universe(path)?,
+        (None, false, _) => universe::Universe::from_profile(&profile)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?,
+        (None, true, Start::Flat) => {
+            universe::Universe::new(opt.timespan.unwrap(), opt.length.unwrap())
+        }
+        (None, true, Start::Random) => universe::Universe::random(
+            opt.timespan.unwrap(),
+            opt.length.unwrap(),
+            length_distribution,
+        ),
+        (None, true, Start::Pinched) => {
+            universe::Universe::pinched(opt.timespan.unwrap(), opt.length.unwrap())
+        }
+    };
+    let timespan = universe.timespan();
+    let length = universe.size() / (2 * timespan);
+    let sweep = universe.size();
 
     // check parameters
+    assert!(
+        opt.timespan.unwrap_or(timespan) == timespan && opt.length.unwrap_or(length) == length,
+        "given timespan and length do not match the initial state (t = {}, l = {})",
+        timespan,
+        length
+    );
     assert!(
         (0.0..=1.0).contains(&move_ratio),
         "given move ratio ({}) is outside valid range [0.0, 1.0]",
@@ -93,8 +284,39 @@ fn measurement() -> std::io::Result<()> {
         move_ratio_eq
     );
 
+    // the move ratio only sets the flip and shard weights if --move does not override them
+    assert!(
+        !tune_move_ratio
+            || !move_weights
+                .iter()
+                .any(|MoveWeight(kind, _)| matches!(kind, MoveKind::Flip | MoveKind::Shard)),
+        "the move ratio cannot be tuned when the flip or shard weight is given explicitly"
+    );
+
+    for &ratio in &tuning_ratios {
+        assert!(
+            (0.0..=1.0).contains(&ratio),
+            "given tuning move ratio ({}) is outside valid range [0.0, 1.0]",
+            ratio
+        );
+    }
+
+    // the direct sampler replaces the Markov chain between measurements, at the exact volume of
+    // the universe, which need not be a multiple of the timespan
+    let sampler = match direct_sampling {
+        true => Some(
+            DirectSampler::new(timespan, universe.size() / 2)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?,
+        ),
+        false => None,
+    };
+
+    // the moves of the equilibration and measurement phase, which keep their own statistics
+    let mut moves_eq = Moves::from_ratio(move_ratio_eq, &move_weights);
+    let mut moves = Moves::from_ratio(move_ratio, &move_weights);
+
     // determine the number of timesteps between measurements
-    let pause = match is_measurement {
+    let mut pause = match is_measurement {
         true => (opt.pause * sweep as f32) as usize,
         false => 1,
     };
@@ -121,21 +343,49 @@ fn measurement() -> std::io::Result<()> {
         "{}_t{}_l{}_n{}_r{}_{}",
         data_type, timespan, length, n_save, move_ratio, now
     );
-    let data_path = format!("{}/{}.csv", output_folder, name);
+    let data_path = format!("{}/{}.{}", output_folder, name, output_format.extension());
     let config_path = format!("{}/{}.json", output_folder, name);
 
+    // keep track of the Markov chain progress for the measurement metadata and snapshots
+    let start = Instant::now();
+    let mut step = 0;
+    let mut snapshots = Snapshots {
+        interval: snapshot_interval * sweep,
+        frame: 0,
+        previous: None,
+        previous_origin: None,
+        graphs: graphs.clone(),
+        graph_format,
+        embedding,
+        output_folder: output_folder.clone(),
+        name: name.clone(),
+    };
+
     if visualisation {
-        // big bang
-        let mut universe = universe::Universe::new(timespan, length);
+        run_chain(
+            &mut universe,
+            &mut moves_eq,
+            n_save * sweep,
+            rejection_free,
+            &mut step,
+            &mut snapshots,
+        )?;
 
-        for _ in 0..(n_save * sweep) {
-            universe.mcmc_step(move_ratio_eq);
+        for &kind in &graphs {
+            write_graph(&universe, 0, kind, graph_format, &output_folder, &name)?;
         }
-
-        write_triangulation_mesh(&universe, &format!("{}/mesh_{}.csv", output_folder, name))
+        if save_state {
+            state::write_universe(&universe, &format!("{}/state_{}.txt", output_folder, name))?;
+        }
+        write_triangulation_mesh(
+            &universe,
+            0,
+            embedding,
+            &format!("{}/mesh_{}.csv", output_folder, name),
+        )
     } else {
         // put everything in json format (TODO: No need to do this, serde can do this from Opt)
-        let measurement = json!({
+        let mut measurement = json!({
             "name": name,
             "is_measurement": is_measurement,
             "timespan": timespan,
@@ -144,54 +394,449 @@ fn measurement() -> std::io::Result<()> {
             "n_save": n_save,
             "pause": pause,
             "move_ratio_eq": move_ratio_eq,
+            "move_weights": move_weights,
+            "tune_move_ratio": tune_move_ratio,
+            "tuning_ratios": tuning_ratios,
+            "tuning_sweeps": tuning_sweeps,
             "eq_sweeps": eq_sweeps,
+            "auto_equilibrate": auto_equilibrate,
+            "max_eq_sweeps": max_eq_sweeps,
+            "auto_pause": auto_pause,
+            "pilot_sweeps": pilot_sweeps,
+            "direct_sampling": direct_sampling,
+            "rejection_free": rejection_free,
+            "initial_state": initial_state,
+            "start": start_condition,
+            "length_distribution": length_distribution,
+            "profile": profile,
+            "output_std": output_std,
+            "statistics": statistics,
+            "output_format": output_format,
+            "snapshot_interval": snapshot_interval,
+            "observables": observable_kinds,
+            "batch_count": batch_count,
+            "regularization": regularization,
+            "max_distance": max_distance,
+            "eigenvalues": eigenvalues,
+            "alignment": alignment,
         });
 
-        std::fs::write(config_path, measurement.to_string())?;
-    
-
-        // big bang
-        let mut universe = universe::Universe::new(timespan, length);
+        std::fs::write(&config_path, measurement.to_string())?;
 
         // do equilibration phase if required
-        if is_measurement {
-            for _ in 0..(eq_sweeps * sweep) {
-                universe.mcmc_step(move_ratio_eq);
+        if is_measurement && !direct_sampling && !auto_equilibrate {
+            run_chain(
+                &mut universe,
+                &mut moves_eq,
+                eq_sweeps * sweep,
+                rejection_free,
+                &mut step,
+                &mut snapshots,
+            )?;
+        }
+
+        // or keep equilibrating until the initial transient of the monitored observables is
+        // short compared to the whole phase, looking for it whenever the series has doubled
+        if is_measurement && !direct_sampling && auto_equilibrate {
+            let mut monitor = Monitor::new(sweep);
+            let mut next_check = (eq_sweeps * sweep / monitor.interval).max(100);
+            let mut transient = 0;
+            let mut equilibrated = false;
+            while !equilibrated && step < max_eq_sweeps * sweep {
+                run_chain(
+                    &mut universe,
+                    &mut moves_eq,
+                    monitor.interval,
+                    rejection_free,
+                    &mut step,
+                    &mut snapshots,
+                )?;
+                monitor.record(&universe);
+                if monitor.len() < next_check && step < max_eq_sweeps * sweep {
+                    continue;
+                }
+                next_check = 2 * monitor.len();
+
+                // as a rule of thumb, equilibrate for at least five times the transient
+                transient = monitor.truncation_point();
+                equilibrated = 5 * transient <= monitor.len();
             }
+            if !equilibrated {
+                eprintln!(
+                    "the equilibration phase did not reach equilibrium within {} sweeps",
+                    max_eq_sweeps
+                );
+            }
+            measurement["equilibration"] = json!({
+                "sweeps": step as f64 / sweep as f64,
+                "equilibration_time": (transient * monitor.interval) as f64 / sweep as f64,
+                "equilibrated": equilibrated,
+            });
+            std::fs::write(&config_path, measurement.to_string())?;
+        }
+
+        // pick the move ratio that decorrelates fastest for the measurement phase
+        if is_measurement && !direct_sampling && tune_move_ratio {
+            let (best_ratio, tuning) = tune_ratio(
+                &universe,
+                &tuning_ratios,
+                &move_weights,
+                tuning_sweeps,
+                rejection_free,
+            );
+            move_ratio = best_ratio;
+            moves = Moves::from_ratio(move_ratio, &move_weights);
+            measurement["move_ratio"] = json!(move_ratio);
+            measurement["tuning"] = tuning;
+            std::fs::write(&config_path, measurement.to_string())?;
+        }
+
+        // estimate the autocorrelation time of the monitored observables in a pilot phase,
+        // looking at them every tenth of a sweep, and space the measurements accordingly
+        if let (true, Some(multiple)) = (is_measurement && !direct_sampling, auto_pause) {
+            let mut monitor = Monitor::new(sweep);
+            for _ in 0..(pilot_sweeps * sweep / monitor.interval) {
+                run_chain(
+                    &mut universe,
+                    &mut moves,
+                    monitor.interval,
+                    rejection_free,
+                    &mut step,
+                    &mut snapshots,
+                )?;
+                monitor.record(&universe);
+            }
+            let (tau_stdev, tau_max) = monitor.autocorrelation_times();
+            pause = ((multiple as f64 * tau_stdev.max(tau_max) * sweep as f64) as usize).max(1);
+
+            measurement["pause"] = json!(pause);
+            measurement["pilot"] = json!({
+                "autocorrelation_time": {
+                    "stdev": tau_stdev,
+                    "max": tau_max,
+                },
+                "pause": pause as f64 / sweep as f64,
+            });
+            std::fs::write(&config_path, measurement.to_string())?;
         }
 
         // open buffer to write into
         let mut output = BufWriter::new(File::create(&data_path).unwrap());
+        // the statistics are written in single precision, as the standard deviation always was
+        if output_format == OutputFormat::Npy {
+            match statistics.len() {
+                0 => output::write_npy_header(&mut output, "<u8", &[n_save, timespan])?,
+                1 => output::write_npy_header(&mut output, "<f4", &[n_save])?,
+                k => output::write_npy_header(&mut output, "<f4", &[n_save, k])?,
+            }
+        }
+
+        // additional observables get their own file, unless they fit in the JSON Lines records
+        let settings = observables::Settings {
+            timespan,
+            n_save,
+            batch_count,
+            regularization,
+            max_distance,
+            eigenvalues,
+            alignment,
+        };
+        let mut observables: Vec<_> = observable_kinds
+            .iter()
+            .map(|&kind| (kind, kind.create(&settings), None))
+            .collect();
 
         // measurement phase
-        for _ in 0..n_save {
-            for _ in 0..pause {
-                universe.mcmc_step(move_ratio);
+        for i in 0..n_save {
+            match &sampler {
+                Some(sampler) => universe = sampler.sample(),
+                None => {
+                    run_chain(
+                        &mut universe,
+                        &mut moves,
+                        pause,
+                        rejection_free,
+                        &mut step,
+                        &mut snapshots,
+                    )?;
+                }
             }
+
             // do the measurement
             let origin = fastrand::usize(0..sweep);
             let length_profile = universe.length_profile(origin);
+            let mut values = Vec::with_capacity(observables.len());
+            for (kind, observable, observable_output) in observables.iter_mut() {
+                let value = match observable.measure(&universe, &length_profile, origin) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                if output_format == OutputFormat::Jsonl {
+                    values.push((kind.name(), value));
+                    continue;
+                }
+                if observable_output.is_none() {
+                    let observable_path =
+                        format!("{}/{}_{}.jsonl", output_folder, kind.name(), name);
+                    *observable_output = Some(BufWriter::new(File::create(observable_path)?));
+                }
+                if let Some(observable_output) = observable_output {
+                    writeln!(observable_output, "{}", value)?;
+                }
+            }
 
             // write to file
-            if output_std {
-                writeln!(output, "{}, ", length_profile.stdev())?;
-            } else {
-                writeln!(output, "{}", length_profile)?;
+            let only_statistics = !statistics.is_empty();
+            match (output_format, only_statistics) {
+                (OutputFormat::Csv, true) => {
+                    for &statistic in &statistics {
+                        write!(output, "{}, ", length_profile.statistic(statistic) as f32)?;
+                    }
+                    writeln!(output)?;
+                }
+                (OutputFormat::Csv, false) => writeln!(output, "{}", length_profile)?,
+                (OutputFormat::Npy, true) => {
+                    for &statistic in &statistics {
+                        let value = length_profile.statistic(statistic) as f32;
+                        output.write_all(&value.to_le_bytes())?;
+                    }
+                }
+                (OutputFormat::Npy, false) => {
+                    for &length in length_profile.lengths() {
+                        output.write_all(&(length as u64).to_le_bytes())?;
+                    }
+                }
+                (OutputFormat::Jsonl, _) => {
+                    let mut record = json!({
+                        "measurement": i,
+                        "origin": origin,
+                        "elapsed": start.elapsed().as_secs_f64(),
+                    });
+                    // independent samples of the direct sampler have no position in a chain
+                    if sampler.is_none() {
+                        record["step"] = json!(step);
+                        record["sweep"] = json!(step as f64 / sweep as f64);
+                    }
+                    match only_statistics {
+                        true => {
+                            for &statistic in &statistics {
+                                record[statistic.name()] =
+                                    json!(length_profile.statistic(statistic));
+                            }
+                        }
+                        false => record["length_profile"] = json!(length_profile),
+                    }
+                    for (key, value) in values {
+                        record[key] = value;
+                    }
+                    writeln!(output, "{}", record)?;
+                }
             }
         }
 
         // flush buffer
-        output.flush()
+        output.flush()?;
+
+        // add the move statistics to the run metadata
+        measurement["moves"] = json!({
+            "equilibration": moves_eq.summary(),
+            "measurement": moves.summary(),
+        });
+        std::fs::write(&config_path, measurement.to_string())?;
+
+        // write the results accumulated over the whole run
+        for (kind, observable, observable_output) in observables.iter_mut() {
+            if let Some(observable_output) = observable_output.as_mut() {
+                observable_output.flush()?;
+            }
+            if let Some(summary) = observable.finish() {
+                let summary_path = format!("{}/{}_{}.json", output_folder, kind.name(), name);
+                std::fs::write(summary_path, summary.to_string())?;
+            }
+        }
+
+        for &kind in &graphs {
+            write_graph(&universe, 0, kind, graph_format, &output_folder, &name)?;
+        }
+        if save_state {
+            state::write_universe(&universe, &format!("{}/state_{}.txt", output_folder, name))?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a numbered series of meshes (and graphs) during a run, where every frame is aligned in
+/// time and space with the previous one such that the geometry does not jump around between frames
+struct Snapshots {
+    interval: usize,
+    frame: usize,
+    previous: Option<universe::LengthProfile>,
+    previous_origin: Option<usize>,
+    graphs: Vec<GraphKind>,
+    graph_format: GraphFormat,
+    embedding: Embedding,
+    output_folder: String,
+    name: String,
+}
+
+impl Snapshots {
+    fn record(&mut self, universe: &universe::Universe, step: usize) -> std::io::Result<()> {
+        if self.interval == 0 || !step.is_multiple_of(self.interval) {
+            return Ok(());
+        }
+
+        let origin = self.aligned_origin(universe);
+        let name = format!("{}_{:05}", self.name, self.frame);
+        for &kind in &self.graphs {
+            write_graph(
+                universe,
+                origin,
+                kind,
+                self.graph_format,
+                &self.output_folder,
+                &name,
+            )?;
+        }
+        write_triangulation_mesh(
+            universe,
+            origin,
+            self.embedding,
+            &format!("{}/mesh_{}.csv", self.output_folder, name),
+        )?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    fn aligned_origin(&mut self, universe: &universe::Universe) -> usize {
+        // start from the slab for which the length profile matches the previous frame best
+        let profile = universe.length_profile(0);
+        let t_max = profile.len();
+        let shift = match &self.previous {
+            None => 0,
+            Some(previous) => (0..t_max)
+                .min_by_key(|&shift| {
+                    (0..t_max)
+                        .map(|t| {
+                            let diff = profile[(t + shift) % t_max] as isize - previous[t] as isize;
+                            diff * diff
+                        })
+                        .sum::<isize>()
+                })
+                .unwrap_or(0),
+        };
+        let slab_origin = universe.slab_origin(0, shift);
+
+        // and within that slab from the same triangle as the previous frame, which moves along
+        // with the geometry as long as it stays in the slab (it only leaves by a shard move)
+        let origin = match self.previous_origin {
+            Some(previous_origin) if universe.in_same_slab(previous_origin, slab_origin) => {
+                previous_origin
+            }
+            _ => slab_origin,
+        };
+        self.previous = Some(universe.length_profile(origin));
+        self.previous_origin = Some(origin);
+        origin
     }
 }
 
-fn write_triangulation_mesh(universe: &universe::Universe, data_path: &str) -> std::io::Result<()> {
-    let mesh = universe.torus_triangle_coordinates();
+/// Run a short chain from the given universe at every move ratio, and measure the integrated
+/// autocorrelation time of the slice length standard deviation and the CPU time per sweep,
+/// giving the move ratio for which their product is smallest together with the tuning curve
+fn tune_ratio(
+    universe: &universe::Universe,
+    ratios: &[f32],
+    move_weights: &[MoveWeight],
+    sweeps: usize,
+    rejection_free: bool,
+) -> (f32, serde_json::Value) {
+    let mut autocorrelation_times = Vec::with_capacity(ratios.len());
+    let mut seconds_per_sweep = Vec::with_capacity(ratios.len());
+    let mut costs = Vec::with_capacity(ratios.len());
+    for &ratio in ratios {
+        let mut moves = Moves::from_ratio(ratio, move_weights);
+        let (tau, seconds) = chain_cost(universe, &mut moves, sweeps, rejection_free);
+        autocorrelation_times.push(tau);
+        seconds_per_sweep.push(seconds);
+        costs.push(tau * seconds);
+    }
+
+    let best = (0..ratios.len())
+        .min_by(|&i, &j| costs[i].total_cmp(&costs[j]))
+        .expect("no move ratios to tune");
+    let tuning = json!({
+        "move_ratio": ratios,
+        "autocorrelation_time": autocorrelation_times,
+        "seconds_per_sweep": seconds_per_sweep,
+        "seconds_per_autocorrelation_time": costs,
+        "best": ratios[best],
+    });
+    (ratios[best], tuning)
+}
+
+/// Run the Markov chain for the given number of steps, writing snapshots on the way
+fn run_chain(
+    universe: &mut universe::Universe,
+    moves: &mut Moves,
+    steps: usize,
+    rejection_free: bool,
+    step: &mut usize,
+    snapshots: &mut Snapshots,
+) -> std::io::Result<()> {
+    let end = *step + steps;
+    while *step < end {
+        if rejection_free {
+            // skipping steps must not skip a snapshot
+            let next_snapshot = match snapshots.interval {
+                0 => end,
+                interval => (*step / interval + 1) * interval,
+            };
+            *step += universe.rejection_free_step(moves, next_snapshot.min(end) - *step);
+        } else {
+            universe.mcmc_step(moves);
+            *step += 1;
+        }
+        snapshots.record(universe, *step)?;
+    }
+    Ok(())
+}
+
+fn write_triangulation_mesh(
+    universe: &universe::Universe,
+    origin: usize,
+    embedding: Embedding,
+    data_path: &str,
+) -> std::io::Result<()> {
+    let mesh = universe.triangle_coordinates(origin, embedding);
 
-    
     let mut output = BufWriter::new(File::create(data_path).unwrap());
     for triangle in mesh {
         writeln!(output, "{},{},{}", triangle.0, triangle.1, triangle.2)?;
     }
     output.flush()
-}
\ No newline at end of file
+}
+
+fn write_graph(
+    universe: &universe::Universe,
+    origin: usize,
+    kind: GraphKind,
+    format: GraphFormat,
+    output_folder: &str,
+    name: &str,
+) -> std::io::Result<()> {
+    let graph = match kind {
+        GraphKind::Dual => universe.dual_graph(origin),
+        GraphKind::Vertex => universe.vertex_graph(origin),
+    };
+
+    let data_path = format!(
+        "{}/{}_{}.{}",
+        output_folder,
+        kind.name(),
+        name,
+        format.extension()
+    );
+    let mut output = BufWriter::new(File::create(data_path).unwrap());
+    graph.write(&mut output, format)?;
+    output.flush()
+}
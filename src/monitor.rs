@@ -0,0 +1,81 @@
+use crate::moves::Moves;
+use crate::statistics;
+use crate::universe::Universe;
+use std::time::Instant;
+
+/// The observables followed to judge equilibration and autocorrelation, being the standard
+/// deviation and maximum of the slice lengths, recorded every tenth of a sweep
+pub struct Monitor {
+    sweep: usize,
+    pub interval: usize,
+    stdevs: Vec<f64>,
+    maxima: Vec<f64>,
+}
+
+impl Monitor {
+    pub fn new(sweep: usize) -> Self {
+        Monitor {
+            sweep,
+            interval: (sweep / 10).max(1),
+            stdevs: Vec::new(),
+            maxima: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, universe: &Universe) {
+        let length_profile = universe.length_profile(0);
+        self.stdevs.push(length_profile.stdev());
+        self.maxima.push(length_profile.max() as f64);
+    }
+
+    pub fn len(&self) -> usize {
+        self.stdevs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stdevs.is_empty()
+    }
+
+    /// The number of recorded points to discard as initial transient of either observable
+    pub fn truncation_point(&self) -> usize {
+        statistics::truncation_point(&self.stdevs).max(statistics::truncation_point(&self.maxima))
+    }
+
+    /// The integrated autocorrelation times of the standard deviation and maximum in sweeps
+    pub fn autocorrelation_times(&self) -> (f64, f64) {
+        let in_sweeps = self.interval as f64 / self.sweep as f64;
+        (
+            statistics::integrated_autocorrelation_time(&self.stdevs) * in_sweeps,
+            statistics::integrated_autocorrelation_time(&self.maxima) * in_sweeps,
+        )
+    }
+}
+
+/// Run a short chain from (a copy of) the given universe, giving the integrated autocorrelation
+/// time of the slice length standard deviation in sweeps and the CPU time per sweep
+pub fn chain_cost(
+    universe: &Universe,
+    moves: &mut Moves,
+    sweeps: usize,
+    rejection_free: bool,
+) -> (f64, f64) {
+    let mut universe = universe.clone();
+    let mut monitor = Monitor::new(universe.size());
+    let start = Instant::now();
+    for _ in 0..(sweeps * monitor.sweep / monitor.interval) {
+        let mut step = 0;
+        while step < monitor.interval {
+            step += match rejection_free {
+                true => universe.rejection_free_step(moves, monitor.interval - step),
+                false => {
+                    universe.mcmc_step(moves);
+                    1
+                }
+            };
+        }
+        monitor.record(&universe);
+    }
+    let seconds = start.elapsed().as_secs_f64() / sweeps as f64;
+    let (tau, _) = monitor.autocorrelation_times();
+    (tau, seconds)
+}
@@ -0,0 +1,356 @@
+use crate::universe::Universe;
+use serde_json::{json, Map, Value};
+use std::str::FromStr;
+
+/// A Markov chain move, which proposes a change of the triangulation that is then either
+/// accepted and applied, or rejected leaving the triangulation as it was
+pub trait Move {
+    type Proposal;
+
+    /// Propose a change, or None if the move is not possible in the current triangulation
+    fn propose(&self, universe: &Universe) -> Option<Self::Proposal>;
+
+    /// Whether to carry out the proposal, which must keep detailed balance
+    fn accept(&self, universe: &Universe, proposal: &Self::Proposal) -> bool;
+
+    fn apply(&self, universe: &mut Universe, proposal: Self::Proposal);
+}
+
+/// A move with its proposal type erased, such that different moves fit in one registry
+trait Attempt {
+    /// Perform one propose, accept/reject and apply cycle, returning whether the move was accepted
+    fn attempt(&self, universe: &mut Universe) -> bool;
+}
+
+impl<M: Move> Attempt for M {
+    fn attempt(&self, universe: &mut Universe) -> bool {
+        match self.propose(universe) {
+            Some(proposal) if self.accept(universe, &proposal) => {
+                self.apply(universe, proposal);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Flip an adjacent up and down triangle, which only works if they have different orientations
+pub struct TriangleFlip;
+
+impl Move for TriangleFlip {
+    type Proposal = usize;
+
+    fn propose(&self, universe: &Universe) -> Option<usize> {
+        Some(universe.sample_uniform())
+    }
+
+    fn accept(&self, universe: &Universe, &left: &usize) -> bool {
+        // only flip when possible, do nothing otherwise
+        // this is to ensure detailed balance
+        universe.is_flippable(left)
+    }
+
+    fn apply(&self, universe: &mut Universe, left: usize) {
+        universe.triangle_flip(left);
+    }
+}
+
+/// Move a shard (an up triangle with the down triangle below, at an order four vertex)
+/// to a uniformly chosen position in any slab
+pub struct ShardMove;
+
+impl Move for ShardMove {
+    type Proposal = (usize, usize);
+
+    fn propose(&self, universe: &Universe) -> Option<(usize, usize)> {
+        // without order four vertices there is nothing to move, and the attempt is rejected
+        // rather than replaced by a flip as it used to be: a flip can create or remove the only
+        // order four vertex, and would then be proposed more often in one direction than in the
+        // other, which breaks detailed balance
+        if !universe.has_order_four() {
+            return None;
+        }
+        let shard_up = universe.sample_shard();
+        Some((shard_up, universe.sample_dest(shard_up)))
+    }
+
+    fn accept(&self, universe: &Universe, &(shard_up, dest_up): &(usize, usize)) -> bool {
+        // moving the shard to its original position changes nothing
+        !universe.is_shard_position(shard_up, dest_up)
+    }
+
+    fn apply(&self, universe: &mut Universe, (shard_up, dest_up): (usize, usize)) {
+        universe.shard_move(shard_up, dest_up);
+    }
+}
+
+/// The largest number of shards moved at once by the cluster shard move
+const MAX_RUN: usize = 16;
+
+/// Move a run of up to MAX_RUN adjacent shards (in one slab, at adjacent order four vertices) to
+/// a uniformly chosen position in any slab, changing the lengths of two slices by the length of
+/// the run at once. Like for the shard move, the number of order four vertices does not change,
+/// and the run starts at a uniformly chosen order four vertex with a uniformly chosen length, so
+/// the move back is proposed with the same probability and every valid run is accepted.
+pub struct ClusterShardMove;
+
+impl Move for ClusterShardMove {
+    type Proposal = (Vec<usize>, usize);
+
+    fn propose(&self, universe: &Universe) -> Option<(Vec<usize>, usize)> {
+        if !universe.has_order_four() {
+            return None;
+        }
+        let count = fastrand::usize(1..=MAX_RUN);
+        let run = universe.shard_run(universe.sample_shard(), count)?;
+        let dest_up = loop {
+            let dest_up = universe.sample_dest(run[0]);
+            if !run.contains(&dest_up) {
+                break dest_up;
+            }
+        };
+        Some((run, dest_up))
+    }
+
+    fn accept(&self, universe: &Universe, (run, dest_up): &(Vec<usize>, usize)) -> bool {
+        // moving the run to its original position changes nothing
+        !universe.is_shard_position(run[0], *dest_up)
+    }
+
+    fn apply(&self, universe: &mut Universe, (run, dest_up): (Vec<usize>, usize)) {
+        universe.shard_run_move(&run, dest_up);
+    }
+}
+
+/// Redraw a whole slab, chosen through a uniformly random triangle, from all slabs that fit
+/// between the slices below and above it, which is always accepted
+pub struct SlabHeatbath;
+
+impl Move for SlabHeatbath {
+    type Proposal = usize;
+
+    fn propose(&self, universe: &Universe) -> Option<usize> {
+        Some(universe.sample_uniform())
+    }
+
+    fn accept(&self, _universe: &Universe, _label: &usize) -> bool {
+        // the new slab is drawn from its exact conditional distribution
+        true
+    }
+
+    fn apply(&self, universe: &mut Universe, label: usize) {
+        universe.slab_heatbath(label);
+    }
+}
+
+/// The moves that can be selected
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoveKind {
+    Flip,
+    Shard,
+    Heatbath,
+    Cluster,
+}
+
+/// A move together with its relative weight, written as "name=weight"
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub struct MoveWeight(pub MoveKind, pub f32);
+
+/// The registry of moves used in a Markov chain, choosing a move at random by weight for every
+/// step while keeping track of how often each move was attempted and accepted
+pub struct Moves {
+    entries: Vec<Entry>,
+    total_weight: f32,
+}
+
+struct Entry {
+    kind: MoveKind,
+    weight: f32,
+    attempted: usize,
+    accepted: usize,
+    move_impl: Box<dyn Attempt>,
+}
+
+impl MoveKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MoveKind::Flip => "flip",
+            MoveKind::Shard => "shard",
+            MoveKind::Heatbath => "heatbath",
+            MoveKind::Cluster => "cluster",
+        }
+    }
+
+    fn create(&self) -> Box<dyn Attempt> {
+        match self {
+            MoveKind::Flip => Box::new(TriangleFlip),
+            MoveKind::Shard => Box::new(ShardMove),
+            MoveKind::Heatbath => Box::new(SlabHeatbath),
+            MoveKind::Cluster => Box::new(ClusterShardMove),
+        }
+    }
+}
+
+impl Moves {
+    pub fn new(weights: &[MoveWeight]) -> Self {
+        let entries: Vec<Entry> = weights
+            .iter()
+            .filter(|MoveWeight(_, weight)| *weight > 0.0)
+            .map(|&MoveWeight(kind, weight)| Entry {
+                kind,
+                weight,
+                attempted: 0,
+                accepted: 0,
+                move_impl: kind.create(),
+            })
+            .collect();
+        let total_weight = entries.iter().map(|entry| entry.weight).sum();
+        assert!(!entries.is_empty(), "no move has a positive weight");
+        Moves {
+            entries,
+            total_weight,
+        }
+    }
+
+    /// The triangle flip and shard move, where the move ratio is the probability of a shard move,
+    /// with the weights of any further moves given explicitly (overriding the move ratio)
+    pub fn from_ratio(move_ratio: f32, extra: &[MoveWeight]) -> Self {
+        let mut weights = vec![
+            MoveWeight(MoveKind::Flip, 1.0 - move_ratio),
+            MoveWeight(MoveKind::Shard, move_ratio),
+        ];
+        for &MoveWeight(kind, weight) in extra {
+            match weights.iter_mut().find(|MoveWeight(k, _)| *k == kind) {
+                Some(existing) => existing.1 = weight,
+                None => weights.push(MoveWeight(kind, weight)),
+            }
+        }
+        Moves::new(&weights)
+    }
+
+    /// Attempt a single move chosen at random according to the weights
+    pub fn step(&mut self, universe: &mut Universe) {
+        let mut choice = fastrand::f32() * self.total_weight;
+        let last = self.entries.len() - 1;
+        let index = (0..last)
+            .find(|&i| {
+                choice -= self.entries[i].weight;
+                choice < 0.0
+            })
+            .unwrap_or(last);
+
+        let entry = &mut self.entries[index];
+        entry.attempted += 1;
+        if entry.move_impl.attempt(universe) {
+            entry.accepted += 1;
+        }
+    }
+
+    /// Advance by at least one and at most the given number of steps in a way that is equivalent
+    /// to that many single steps, returning the number of steps taken. Instead of uniformly
+    /// sampled triangles that mostly cannot be flipped, a flippable pair is sampled directly, and
+    /// the steps that would have been rejected flips are skipped, their number being geometrically
+    /// distributed (n-fold way). Any state is kept for as many steps as it would have been in the
+    /// Markov chain, so measurements every so many steps are distributed in the same way.
+    pub fn step_rejection_free(&mut self, universe: &mut Universe, max_steps: usize) -> usize {
+        let flip = match self
+            .entries
+            .iter()
+            .position(|entry| entry.kind == MoveKind::Flip)
+        {
+            Some(flip) => flip,
+            None => {
+                self.step(universe);
+                return 1;
+            }
+        };
+
+        // the probability that a step does anything other than a rejected flip
+        let flip_weight = (self.entries[flip].weight / self.total_weight) as f64;
+        let flip_probability =
+            flip_weight * universe.flippable_count() as f64 / universe.size() as f64;
+        let probability = 1.0 - flip_weight + flip_probability;
+        let skipped = match probability < 1.0 {
+            true => ((1.0 - fastrand::f64()).ln() / (1.0 - probability).ln()) as usize,
+            false => 0,
+        };
+        if skipped >= max_steps {
+            self.entries[flip].attempted += max_steps;
+            return max_steps;
+        }
+        self.entries[flip].attempted += skipped;
+
+        // the first step that does not skip is a flip, or any other move chosen by weight
+        let index = match fastrand::f64() * probability < flip_probability {
+            true => flip,
+            false => {
+                let mut choice = fastrand::f32() * (self.total_weight - self.entries[flip].weight);
+                let others: Vec<usize> = (0..self.entries.len()).filter(|&i| i != flip).collect();
+                let last = others[others.len() - 1];
+                others
+                    .into_iter()
+                    .find(|&i| {
+                        choice -= self.entries[i].weight;
+                        choice < 0.0
+                    })
+                    .unwrap_or(last)
+            }
+        };
+        let entry = &mut self.entries[index];
+        entry.attempted += 1;
+        if index == flip {
+            universe.triangle_flip(universe.sample_flippable());
+            entry.accepted += 1;
+        } else if entry.move_impl.attempt(universe) {
+            entry.accepted += 1;
+        }
+        skipped + 1
+    }
+
+    /// The weight, number of attempted, rejected and accepted moves for every move
+    pub fn summary(&self) -> Value {
+        let mut summary = Map::new();
+        for entry in &self.entries {
+            summary.insert(
+                entry.kind.name().to_string(),
+                json!({
+                    "weight": entry.weight / self.total_weight,
+                    "attempted": entry.attempted,
+                    "rejected": entry.attempted - entry.accepted,
+                    "accepted": entry.accepted,
+                }),
+            );
+        }
+        Value::Object(summary)
+    }
+}
+
+impl FromStr for MoveKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flip" => Ok(MoveKind::Flip),
+            "shard" => Ok(MoveKind::Shard),
+            "heatbath" => Ok(MoveKind::Heatbath),
+            "cluster" => Ok(MoveKind::Cluster),
+            _ => Err(format!("unknown move '{}'", s)),
+        }
+    }
+}
+
+impl FromStr for MoveWeight {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, weight) = s
+            .split_once('=')
+            .ok_or(format!("expected 'name=weight' for a move, got '{}'", s))?;
+        let weight: f32 = weight
+            .parse()
+            .map_err(|_| format!("could not read the weight of move '{}'", kind))?;
+        if weight < 0.0 {
+            return Err(format!("the weight of move '{}' is negative", kind));
+        }
+        Ok(MoveWeight(kind.parse()?, weight))
+    }
+}
@@ -0,0 +1,151 @@
+mod aligned_profile;
+mod coordination;
+mod correlator;
+mod effective_action;
+mod histogram;
+mod laplacian;
+mod light_cone;
+mod loops;
+mod ricci;
+mod transfer_matrix;
+
+pub use aligned_profile::Alignment;
+
+use crate::universe::{LengthProfile, Universe};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// An observable measured next to the length profile at every measurement
+pub trait Observable {
+    /// Measure the observable, using the same origin as the length profile, giving the
+    /// value to write out for this measurement if there is one
+    fn measure(
+        &mut self,
+        universe: &Universe,
+        length_profile: &LengthProfile,
+        origin: usize,
+    ) -> Option<Value>;
+
+    /// Results accumulated over all measurements, written at the end of a run
+    fn finish(&self) -> Option<Value> {
+        None
+    }
+}
+
+/// The run parameters needed to set up the observables
+pub struct Settings {
+    pub timespan: usize,
+    pub n_save: usize,
+    pub batch_count: usize,
+    pub regularization: f64,
+    pub max_distance: usize,
+    pub eigenvalues: usize,
+    pub alignment: Alignment,
+}
+
+/// Spreads the measurements of a run evenly over a number of batches, such that the spread of the
+/// estimates of the batches gives the statistical error
+struct Batches {
+    count: usize,
+    n_save: usize,
+    measurements: usize,
+}
+
+impl Batches {
+    fn new(settings: &Settings) -> Self {
+        Batches {
+            count: settings.batch_count.max(1),
+            n_save: settings.n_save,
+            measurements: 0,
+        }
+    }
+
+    /// The batch the next measurement goes into
+    fn next_index(&mut self) -> usize {
+        let index = (self.measurements * self.count / self.n_save.max(1)).min(self.count - 1);
+        self.measurements += 1;
+        index
+    }
+
+    /// The standard error of the mean of the estimates of the batches, or NaN for a single batch
+    fn error(estimates: &[f64]) -> f64 {
+        let b = estimates.len() as f64;
+        let mean = estimates.iter().sum::<f64>() / b;
+        let variance = estimates.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+        match estimates.len() {
+            0 | 1 => f64::NAN,
+            _ => (variance / (b * (b - 1.0))).sqrt(),
+        }
+    }
+}
+
+/// The additional observables that can be selected
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObservableKind {
+    Coordination,
+    Correlator,
+    EffectiveAction,
+    TransferMatrix,
+    LightCone,
+    Ricci,
+    Loops,
+    Laplacian,
+    AlignedProfile,
+    Histogram,
+}
+
+impl ObservableKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ObservableKind::Coordination => "coordination",
+            ObservableKind::Correlator => "correlator",
+            ObservableKind::EffectiveAction => "effective_action",
+            ObservableKind::TransferMatrix => "transfer_matrix",
+            ObservableKind::LightCone => "light_cone",
+            ObservableKind::Ricci => "ricci",
+            ObservableKind::Loops => "loops",
+            ObservableKind::Laplacian => "laplacian",
+            ObservableKind::AlignedProfile => "aligned_profile",
+            ObservableKind::Histogram => "histogram",
+        }
+    }
+
+    pub fn create(&self, settings: &Settings) -> Box<dyn Observable> {
+        match self {
+            ObservableKind::Coordination => Box::new(coordination::Coordination),
+            ObservableKind::Correlator => Box::new(correlator::Correlator::new(settings)),
+            ObservableKind::EffectiveAction => {
+                Box::new(effective_action::EffectiveAction::new(settings))
+            }
+            ObservableKind::TransferMatrix => Box::new(transfer_matrix::TransferMatrix::new()),
+            ObservableKind::LightCone => Box::new(light_cone::LightCone),
+            ObservableKind::Ricci => Box::new(ricci::Ricci::new(settings)),
+            ObservableKind::Loops => Box::new(loops::Loops),
+            ObservableKind::Laplacian => Box::new(laplacian::Laplacian::new(settings)),
+            ObservableKind::AlignedProfile => {
+                Box::new(aligned_profile::AlignedProfile::new(settings))
+            }
+            ObservableKind::Histogram => Box::new(histogram::Histogram::new()),
+        }
+    }
+}
+
+impl FromStr for ObservableKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "coordination" => Ok(ObservableKind::Coordination),
+            "correlator" => Ok(ObservableKind::Correlator),
+            "effective_action" => Ok(ObservableKind::EffectiveAction),
+            "transfer_matrix" => Ok(ObservableKind::TransferMatrix),
+            "light_cone" => Ok(ObservableKind::LightCone),
+            "ricci" => Ok(ObservableKind::Ricci),
+            "loops" => Ok(ObservableKind::Loops),
+            "laplacian" => Ok(ObservableKind::Laplacian),
+            "aligned_profile" => Ok(ObservableKind::AlignedProfile),
+            "histogram" => Ok(ObservableKind::Histogram),
+            _ => Err(format!("unknown observable '{}'", s)),
+        }
+    }
+}
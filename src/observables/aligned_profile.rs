@@ -0,0 +1,104 @@
+use super::{Batches, Observable, Settings};
+use crate::universe::{LengthProfile, Universe};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// Which feature of the length profile is kept at a fixed slice when averaging
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Alignment {
+    Centre,
+    Maximum,
+}
+
+/// The average length profile, with every profile first rotated such that its centre of volume
+/// (or maximum) sits at the middle slice, accumulated in batches of measurements for the errors
+pub struct AlignedProfile {
+    timespan: usize,
+    alignment: Alignment,
+    batching: Batches,
+    counts: Vec<usize>,
+    sums: Vec<Vec<f64>>,
+}
+
+impl AlignedProfile {
+    pub fn new(settings: &Settings) -> Self {
+        let batch_count = settings.batch_count.max(1);
+        AlignedProfile {
+            timespan: settings.timespan,
+            alignment: settings.alignment,
+            batching: Batches::new(settings),
+            counts: vec![0; batch_count],
+            sums: vec![vec![0.0; settings.timespan]; batch_count],
+        }
+    }
+}
+
+impl Observable for AlignedProfile {
+    fn measure(
+        &mut self,
+        _universe: &Universe,
+        length_profile: &LengthProfile,
+        _origin: usize,
+    ) -> Option<Value> {
+        let index = self.batching.next_index();
+        let feature = match self.alignment {
+            Alignment::Centre => length_profile.centre_of_volume(),
+            Alignment::Maximum => length_profile.maximum(),
+        };
+        let start = (feature + self.timespan - self.timespan / 2) % self.timespan;
+        let aligned = length_profile.rotated(start);
+        self.counts[index] += 1;
+        for (sum, &length) in self.sums[index].iter_mut().zip(aligned.lengths()) {
+            *sum += length as f64;
+        }
+        None
+    }
+
+    fn finish(&self) -> Option<Value> {
+        let batches: Vec<usize> = (0..self.counts.len())
+            .filter(|&b| self.counts[b] > 0)
+            .collect();
+        if batches.is_empty() {
+            return None;
+        }
+
+        // the estimate uses all measurements, the error the spread of the batch averages
+        let total: usize = batches.iter().map(|&b| self.counts[b]).sum();
+        let profile: Vec<f64> = (0..self.timespan)
+            .map(|t| batches.iter().map(|&b| self.sums[b][t]).sum::<f64>() / total as f64)
+            .collect();
+        let error: Vec<f64> = (0..self.timespan)
+            .map(|t| {
+                let averages: Vec<f64> = batches
+                    .iter()
+                    .map(|&batch| self.sums[batch][t] / self.counts[batch] as f64)
+                    .collect();
+                Batches::error(&averages)
+            })
+            .collect();
+
+        Some(json!({
+            "measurements": total,
+            "batch_count": batches.len(),
+            "alignment": self.alignment,
+            "slice": (0..self.timespan).collect::<Vec<_>>(),
+            "profile": profile,
+            "error": error,
+        }))
+    }
+}
+
+impl FromStr for Alignment {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "centre" => Ok(Alignment::Centre),
+            "maximum" => Ok(Alignment::Maximum),
+            _ => Err(format!(
+                "unknown alignment '{}', expected 'centre' or 'maximum'",
+                s
+            )),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use super::Observable;
+use crate::universe::{LengthProfile, Universe};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// Coordination numbers of the vertices, split into past and future timelike links.
+/// The curvature at a vertex with total degree q is given by the deficit 6 - q,
+/// while the past and future coordination numbers are expected to be independently
+/// geometrically distributed in the continuum limit.
+pub struct Coordination;
+
+impl Observable for Coordination {
+    fn measure(
+        &mut self,
+        universe: &Universe,
+        _length_profile: &LengthProfile,
+        origin: usize,
+    ) -> Option<Value> {
+        let graph = universe.vertex_graph(origin);
+        let vertex_count = graph.nodes().len();
+
+        // count the links of every vertex, timelike links point from past to future
+        let mut past = vec![0; vertex_count];
+        let mut future = vec![0; vertex_count];
+        let mut degree = vec![0; vertex_count];
+        for edge in graph.edges() {
+            degree[edge.source] += 1;
+            degree[edge.target] += 1;
+            if edge.kind == "timelike" {
+                future[edge.source] += 1;
+                past[edge.target] += 1;
+            }
+        }
+
+        let mut histogram: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        for vertex in 0..vertex_count {
+            *histogram.entry((past[vertex], future[vertex])).or_insert(0) += 1;
+        }
+        let curvatures: Vec<f64> = degree.iter().map(|&q| 6.0 - q as f64).collect();
+        let mean_curvature = curvatures.iter().sum::<f64>() / vertex_count as f64;
+        let mean_squared_curvature =
+            curvatures.iter().map(|k| k * k).sum::<f64>() / vertex_count as f64;
+
+        Some(json!({
+            "histogram": histogram
+                .iter()
+                .map(|(&(past, future), &count)| [past, future, count])
+                .collect::<Vec<_>>(),
+            "mean_curvature": mean_curvature,
+            "mean_squared_curvature": mean_squared_curvature,
+        }))
+    }
+}
@@ -0,0 +1,110 @@
+use super::{Batches, Observable, Settings};
+use crate::universe::{LengthProfile, Universe};
+use serde_json::{json, Value};
+
+/// The connected two-point function <l(t) l(t + delta)> - <l>^2 of the slice lengths,
+/// averaged over all origins t and accumulated in batches of measurements for the errors
+pub struct Correlator {
+    timespan: usize,
+    batching: Batches,
+    batches: Vec<Batch>,
+}
+
+#[derive(Clone)]
+struct Batch {
+    count: usize,
+    length_sum: f64,
+    products: Vec<f64>,
+}
+
+impl Correlator {
+    pub fn new(settings: &Settings) -> Self {
+        Correlator {
+            timespan: settings.timespan,
+            batching: Batches::new(settings),
+            batches: vec![
+                Batch {
+                    count: 0,
+                    length_sum: 0.0,
+                    products: vec![0.0; settings.timespan],
+                };
+                settings.batch_count.max(1)
+            ],
+        }
+    }
+}
+
+impl Batch {
+    fn correlator(&self, timespan: usize) -> Vec<f64> {
+        let mean = self.length_sum / (self.count * timespan) as f64;
+        self.products
+            .iter()
+            .map(|product| product / self.count as f64 - mean * mean)
+            .collect()
+    }
+}
+
+impl Observable for Correlator {
+    fn measure(
+        &mut self,
+        _universe: &Universe,
+        length_profile: &LengthProfile,
+        _origin: usize,
+    ) -> Option<Value> {
+        let index = self.batching.next_index();
+        let lengths = length_profile.lengths();
+        let t_max = self.timespan;
+        let batch = &mut self.batches[index];
+        batch.count += 1;
+        batch.length_sum += lengths.iter().sum::<usize>() as f64;
+        for (delta, product) in batch.products.iter_mut().enumerate() {
+            *product += (0..t_max)
+                .map(|t| (lengths[t] * lengths[(t + delta) % t_max]) as f64)
+                .sum::<f64>()
+                / t_max as f64;
+        }
+        None
+    }
+
+    fn finish(&self) -> Option<Value> {
+        let batches: Vec<&Batch> = self.batches.iter().filter(|b| b.count > 0).collect();
+        if batches.is_empty() {
+            return None;
+        }
+
+        // the estimate uses all measurements, the error the spread of the batches
+        let mut total = Batch {
+            count: 0,
+            length_sum: 0.0,
+            products: vec![0.0; self.timespan],
+        };
+        for batch in &batches {
+            total.count += batch.count;
+            total.length_sum += batch.length_sum;
+            for (sum, product) in total.products.iter_mut().zip(&batch.products) {
+                *sum += product;
+            }
+        }
+        let correlator = total.correlator(self.timespan);
+
+        let batch_correlators: Vec<Vec<f64>> = batches
+            .iter()
+            .map(|batch| batch.correlator(self.timespan))
+            .collect();
+        let error: Vec<f64> = (0..self.timespan)
+            .map(|delta| {
+                let estimates: Vec<f64> = batch_correlators.iter().map(|c| c[delta]).collect();
+                Batches::error(&estimates)
+            })
+            .collect();
+
+        Some(json!({
+            "measurements": total.count,
+            "batch_count": batches.len(),
+            "mean_length": total.length_sum / (total.count * self.timespan) as f64,
+            "delta": (0..self.timespan).collect::<Vec<_>>(),
+            "correlator": correlator,
+            "error": error,
+        }))
+    }
+}
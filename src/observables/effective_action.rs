@@ -0,0 +1,218 @@
+use super::{Batches, Observable, Settings};
+use crate::linalg;
+use crate::universe::{LengthProfile, Universe};
+use serde_json::{json, Value};
+
+/// Reconstruction of a minisuperspace action S = 1/2 dl^T M dl from the inverse of the
+/// covariance matrix of the slice lengths. Because the total volume is fixed, the covariance
+/// is only inverted on the fluctuations that preserve the volume, where a nearest-neighbour
+/// action with kinetic coupling k and potential coupling m gives M = k Lap + m (1 - J),
+/// with Lap the periodic lattice Laplacian and J the projection on constant profiles.
+pub struct EffectiveAction {
+    timespan: usize,
+    regularization: f64,
+    batching: Batches,
+    batches: Vec<Moments>,
+}
+
+#[derive(Clone)]
+struct Moments {
+    count: usize,
+    sums: Vec<f64>,
+    products: Vec<f64>,
+}
+
+struct Fit {
+    couplings: Vec<f64>,
+    kinetic: f64,
+    potential: f64,
+    residual: f64,
+}
+
+impl EffectiveAction {
+    pub fn new(settings: &Settings) -> Self {
+        EffectiveAction {
+            timespan: settings.timespan,
+            regularization: settings.regularization,
+            batching: Batches::new(settings),
+            batches: vec![Moments::new(settings.timespan); settings.batch_count.max(1)],
+        }
+    }
+
+    fn fit(&self, moments: &Moments) -> Option<Fit> {
+        // covariance matrix, with the constant mode (zero because of the fixed volume) set to
+        // one and regularized by a small multiple of the average variance
+        let t_max = self.timespan;
+        let count = moments.count as f64;
+        let mut covariance = vec![0.0; t_max * t_max];
+        for t in 0..t_max {
+            for s in 0..t_max {
+                covariance[t * t_max + s] = moments.products[t * t_max + s] / count
+                    - moments.sums[t] * moments.sums[s] / (count * count);
+            }
+        }
+        let trace = (0..t_max).map(|t| covariance[t * t_max + t]).sum::<f64>();
+        let epsilon = self.regularization * trace / t_max as f64;
+        for t in 0..t_max {
+            for s in 0..t_max {
+                covariance[t * t_max + s] += 1.0 / t_max as f64;
+            }
+            covariance[t * t_max + t] += epsilon;
+        }
+        let mut inverse = linalg::cholesky_inverse(&covariance, t_max)?;
+        for value in inverse.iter_mut() {
+            *value -= 1.0 / ((1.0 + epsilon) * t_max as f64);
+        }
+
+        // average the couplings over the diagonals, using translation invariance
+        let couplings: Vec<f64> = (0..t_max)
+            .map(|delta| {
+                (0..t_max)
+                    .map(|t| inverse[t * t_max + (t + delta) % t_max])
+                    .sum::<f64>()
+                    / t_max as f64
+            })
+            .collect();
+
+        // least squares fit of the nearest-neighbour structure
+        let laplacian = |delta: usize| match delta {
+            0 => 2.0,
+            1 => -1.0,
+            _ if delta == t_max - 1 => -1.0,
+            _ => 0.0,
+        };
+        let projection = |delta: usize| match delta {
+            0 => 1.0 - 1.0 / t_max as f64,
+            _ => -1.0 / t_max as f64,
+        };
+        let (mut aa, mut ab, mut bb, mut ay, mut by) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for (delta, &coupling) in couplings.iter().enumerate() {
+            let (a, b) = (laplacian(delta), projection(delta));
+            aa += a * a;
+            ab += a * b;
+            bb += b * b;
+            ay += a * coupling;
+            by += b * coupling;
+        }
+        let determinant = aa * bb - ab * ab;
+        let kinetic = (bb * ay - ab * by) / determinant;
+        let potential = (aa * by - ab * ay) / determinant;
+        let residual = (couplings
+            .iter()
+            .enumerate()
+            .map(|(delta, &coupling)| {
+                (coupling - kinetic * laplacian(delta) - potential * projection(delta)).powi(2)
+            })
+            .sum::<f64>()
+            / t_max as f64)
+            .sqrt();
+
+        Some(Fit {
+            couplings,
+            kinetic,
+            potential,
+            residual,
+        })
+    }
+}
+
+impl Moments {
+    fn new(timespan: usize) -> Self {
+        Moments {
+            count: 0,
+            sums: vec![0.0; timespan],
+            products: vec![0.0; timespan * timespan],
+        }
+    }
+
+    fn add(&mut self, other: &Moments, sign: f64) {
+        if sign > 0.0 {
+            self.count += other.count;
+        } else {
+            self.count -= other.count;
+        }
+        for (sum, other_sum) in self.sums.iter_mut().zip(&other.sums) {
+            *sum += sign * other_sum;
+        }
+        for (product, other_product) in self.products.iter_mut().zip(&other.products) {
+            *product += sign * other_product;
+        }
+    }
+}
+
+impl Observable for EffectiveAction {
+    fn measure(
+        &mut self,
+        _universe: &Universe,
+        length_profile: &LengthProfile,
+        _origin: usize,
+    ) -> Option<Value> {
+        let index = self.batching.next_index();
+        let t_max = self.timespan;
+        let lengths = length_profile.lengths();
+        let batch = &mut self.batches[index];
+        batch.count += 1;
+        for t in 0..t_max {
+            batch.sums[t] += lengths[t] as f64;
+            for s in 0..t_max {
+                batch.products[t * t_max + s] += (lengths[t] * lengths[s]) as f64;
+            }
+        }
+        None
+    }
+
+    fn finish(&self) -> Option<Value> {
+        let mut total = Moments::new(self.timespan);
+        for batch in &self.batches {
+            total.add(batch, 1.0);
+        }
+        if total.count < 2 {
+            return None;
+        }
+        let fit = match self.fit(&total) {
+            Some(fit) => fit,
+            None => return Some(json!({ "error": "covariance matrix is not positive definite" })),
+        };
+
+        // jackknife errors, leaving out one batch at a time
+        let jackknife: Vec<Fit> = self
+            .batches
+            .iter()
+            .filter(|batch| batch.count > 0 && batch.count < total.count)
+            .filter_map(|batch| {
+                let mut sample = total.clone();
+                sample.add(batch, -1.0);
+                self.fit(&sample)
+            })
+            .collect();
+        let error = |value: &dyn Fn(&Fit) -> f64| {
+            let n = jackknife.len() as f64;
+            if jackknife.len() < 2 {
+                return f64::NAN;
+            }
+            let mean = jackknife.iter().map(value).sum::<f64>() / n;
+            ((n - 1.0) / n
+                * jackknife
+                    .iter()
+                    .map(|sample| (value(sample) - mean).powi(2))
+                    .sum::<f64>())
+            .sqrt()
+        };
+
+        Some(json!({
+            "measurements": total.count,
+            "batch_count": jackknife.len(),
+            "mean_length": total.sums.iter().sum::<f64>() / (total.count * self.timespan) as f64,
+            "regularization": self.regularization,
+            "couplings": fit.couplings,
+            "couplings_error": (0..self.timespan)
+                .map(|delta| error(&|sample: &Fit| sample.couplings[delta]))
+                .collect::<Vec<_>>(),
+            "kinetic": fit.kinetic,
+            "kinetic_error": error(&|sample: &Fit| sample.kinetic),
+            "potential": fit.potential,
+            "potential_error": error(&|sample: &Fit| sample.potential),
+            "residual": fit.residual,
+        }))
+    }
+}
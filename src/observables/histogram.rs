@@ -0,0 +1,58 @@
+use super::Observable;
+use crate::statistics::Moments;
+use crate::universe::{LengthProfile, Universe};
+use serde_json::{json, Value};
+
+/// The histogram of the slice lengths of all slices over the whole run, with the moments and
+/// Binder cumulant 1 - m4 / (3 m2^2) of the fluctuations of the slice lengths around their mean,
+/// where the skewness, kurtosis and Binder cumulant are 0, 3 and 0 (as for a normal distribution)
+/// if all slices had the same length
+pub struct Histogram {
+    counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Histogram { counts: Vec::new() }
+    }
+}
+
+impl Observable for Histogram {
+    fn measure(
+        &mut self,
+        _universe: &Universe,
+        length_profile: &LengthProfile,
+        _origin: usize,
+    ) -> Option<Value> {
+        for &length in length_profile.lengths() {
+            if length >= self.counts.len() {
+                self.counts.resize(length + 1, 0);
+            }
+            self.counts[length] += 1;
+        }
+        None
+    }
+
+    fn finish(&self) -> Option<Value> {
+        let min = self.counts.iter().position(|&count| count > 0)?;
+        let max = self.counts.len() - 1;
+
+        let total = self.counts.iter().sum::<usize>();
+        let moments = Moments::from_weighted(
+            (min..=max).map(|length| (length as f64, self.counts[length] as f64)),
+        );
+
+        Some(json!({
+            "slices": total,
+            "length": (min..=max).collect::<Vec<_>>(),
+            "count": self.counts[min..=max],
+            "mean": moments.mean,
+            "variance": moments.variance,
+            "skewness": moments.skewness(),
+            "kurtosis": moments.kurtosis(),
+            "min": min,
+            "max": max,
+            "binder": moments.binder_cumulant(),
+        }))
+    }
+}
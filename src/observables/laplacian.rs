@@ -0,0 +1,105 @@
+use super::{Observable, Settings};
+use crate::graph::Graph;
+use crate::linalg;
+use crate::universe::{LengthProfile, Universe};
+use serde_json::{json, Value};
+
+/// The lowest eigenvalues of the graph Laplacian L = D - A of the vertex graph and of the dual
+/// graph, the first one vanishing for a connected graph and the second one being the spectral gap.
+/// These are found as the largest eigenvalues of the pseudo-inverse of the Laplacian.
+pub struct Laplacian {
+    count: usize,
+    measurements: usize,
+    sums: [Vec<f64>; 2],
+    squares: [Vec<f64>; 2],
+}
+
+impl Laplacian {
+    pub fn new(settings: &Settings) -> Self {
+        let zeros = vec![0.0; settings.eigenvalues];
+        Laplacian {
+            count: settings.eigenvalues,
+            measurements: 0,
+            sums: [zeros.clone(), zeros.clone()],
+            squares: [zeros.clone(), zeros],
+        }
+    }
+
+    fn spectrum(&self, graph: &Graph) -> Vec<f64> {
+        let adjacency = graph.adjacency();
+        let n = adjacency.len();
+        let laplacian = |vector: &[f64], product: &mut [f64]| {
+            for (node, neighbours) in adjacency.iter().enumerate() {
+                product[node] = neighbours.len() as f64 * vector[node]
+                    - neighbours.iter().map(|&nbr| vector[nbr]).sum::<f64>();
+            }
+        };
+
+        // the low end of the spectrum is too dense for the Lanczos iteration to resolve, so use
+        // the pseudo-inverse instead, solving the Laplacian orthogonally to the constant zero mode
+        let project = |vector: &mut [f64]| {
+            let mean = vector.iter().sum::<f64>() / n as f64;
+            vector.iter_mut().for_each(|x| *x -= mean);
+        };
+        let inverse = |vector: &[f64], product: &mut [f64]| {
+            let mut rhs = vector.to_vec();
+            project(&mut rhs);
+            product.copy_from_slice(&linalg::conjugate_gradient(laplacian, &rhs, 1e-10));
+            project(product);
+        };
+        let mut start: Vec<f64> = (0..n).map(|_| fastrand::f64() - 0.5).collect();
+        project(&mut start);
+
+        let iterations = (4 * self.count).max(40);
+        let inverse_eigenvalues =
+            linalg::lanczos_largest(inverse, &start, iterations, self.count.saturating_sub(1));
+        std::iter::once(0.0)
+            .chain(inverse_eigenvalues.iter().map(|x| 1.0 / x))
+            .take(self.count)
+            .collect()
+    }
+}
+
+impl Observable for Laplacian {
+    fn measure(
+        &mut self,
+        universe: &Universe,
+        _length_profile: &LengthProfile,
+        origin: usize,
+    ) -> Option<Value> {
+        let vertex = self.spectrum(&universe.vertex_graph(origin));
+        let dual = self.spectrum(&universe.dual_graph(origin));
+
+        self.measurements += 1;
+        for (graph, eigenvalues) in [&vertex, &dual].into_iter().enumerate() {
+            for (k, &eigenvalue) in eigenvalues.iter().enumerate() {
+                self.sums[graph][k] += eigenvalue;
+                self.squares[graph][k] += eigenvalue * eigenvalue;
+            }
+        }
+        Some(json!({ "vertex": vertex, "dual": dual }))
+    }
+
+    fn finish(&self) -> Option<Value> {
+        let n = self.measurements as f64;
+        let average =
+            |graph: usize| -> Vec<f64> { self.sums[graph].iter().map(|s| s / n).collect() };
+        let error = |graph: usize| -> Vec<f64> {
+            (0..self.count)
+                .map(|k| {
+                    let mean = self.sums[graph][k] / n;
+                    let variance = self.squares[graph][k] / n - mean * mean;
+                    (variance.max(0.0) / (n - 1.0)).sqrt()
+                })
+                .collect()
+        };
+
+        Some(json!({
+            "measurements": self.measurements,
+            "vertex": average(0),
+            "vertex_error": error(0),
+            "dual": average(1),
+            "dual_error": error(1),
+        }))
+    }
+}
@@ -0,0 +1,97 @@
+use super::Observable;
+use crate::graph::Graph;
+use crate::universe::{LengthProfile, Universe};
+use serde_json::{json, Value};
+
+/// The number of causal diamonds measured per measurement
+const DIAMOND_COUNT: usize = 16;
+
+/// The future and past light cones of a random vertex, following the timelike links,
+/// given as the number of vertices of the cone in every subsequent slice. Next to that the
+/// volumes of the causal diamonds between the vertex and random vertices in its future cone.
+pub struct LightCone;
+
+impl Observable for LightCone {
+    fn measure(
+        &mut self,
+        universe: &Universe,
+        length_profile: &LengthProfile,
+        origin: usize,
+    ) -> Option<Value> {
+        let graph = universe.vertex_graph(origin);
+        let t_max = length_profile.len();
+        let (future, past) = causal_neighbours(&graph);
+
+        let vertex = fastrand::usize(..graph.nodes().len());
+        let future_cone = cone(&future, vertex, t_max);
+        let past_cone = cone(&past, vertex, t_max);
+
+        // label the future cone with the time difference to the vertex
+        let mut future_delta = vec![None; graph.nodes().len()];
+        for (delta, layer) in future_cone.iter().enumerate() {
+            for &u in layer {
+                future_delta[u] = Some(delta);
+            }
+        }
+
+        // a diamond is the part of the past cone of the tip inside the future cone of the vertex
+        let mut diamonds = Vec::with_capacity(DIAMOND_COUNT);
+        if t_max > 1 {
+            for _ in 0..DIAMOND_COUNT {
+                let delta = fastrand::usize(1..t_max);
+                let layer = &future_cone[delta];
+                let tip = layer[fastrand::usize(..layer.len())];
+                let volume = cone(&past, tip, delta + 1)
+                    .iter()
+                    .enumerate()
+                    .map(|(depth, layer)| {
+                        layer
+                            .iter()
+                            .filter(|&&u| future_delta[u] == Some(delta - depth))
+                            .count()
+                    })
+                    .sum::<usize>();
+                diamonds.push([delta, volume]);
+            }
+        }
+
+        Some(json!({
+            "vertex": vertex,
+            "time": graph.nodes()[vertex].time,
+            "future": future_cone.iter().map(Vec::len).collect::<Vec<_>>(),
+            "past": past_cone.iter().map(Vec::len).collect::<Vec<_>>(),
+            "diamonds": diamonds,
+        }))
+    }
+}
+
+fn causal_neighbours(graph: &Graph) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
+    // timelike links point from past to future
+    let mut future = vec![Vec::new(); graph.nodes().len()];
+    let mut past = vec![Vec::new(); graph.nodes().len()];
+    for edge in graph.edges().iter().filter(|edge| edge.kind == "timelike") {
+        future[edge.source].push(edge.target);
+        past[edge.target].push(edge.source);
+    }
+    (future, past)
+}
+
+fn cone(neighbours: &[Vec<usize>], vertex: usize, depth: usize) -> Vec<Vec<usize>> {
+    // the vertices reachable from the vertex in each number of steps, up to the given depth
+    let mut seen = vec![usize::MAX; neighbours.len()];
+    let mut layers = vec![vec![vertex]];
+    seen[vertex] = 0;
+    for step in 1..depth {
+        let mut layer = Vec::new();
+        for &u in &layers[step - 1] {
+            for &w in &neighbours[u] {
+                if seen[w] != step {
+                    seen[w] = step;
+                    layer.push(w);
+                }
+            }
+        }
+        layers.push(layer);
+    }
+    layers
+}
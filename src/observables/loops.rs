@@ -0,0 +1,111 @@
+use super::Observable;
+use crate::universe::{LengthProfile, Universe};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+/// The shortest non-contractible cycles of the vertex graph: the shortest loop winding once
+/// around space, and the shortest loop winding once around time (with any winding around
+/// space). The ratio of their lengths estimates the modular parameter of the torus.
+pub struct Loops;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Space,
+    Time,
+}
+
+impl Observable for Loops {
+    fn measure(
+        &mut self,
+        universe: &Universe,
+        length_profile: &LengthProfile,
+        origin: usize,
+    ) -> Option<Value> {
+        let graph = universe.vertex_graph(origin);
+        let windings = universe.edge_windings(origin);
+        let mut adjacency = vec![Vec::new(); graph.nodes().len()];
+        for (edge, &(space, time)) in graph.edges().iter().zip(&windings) {
+            adjacency[edge.source].push((edge.target, space, time));
+            adjacency[edge.target].push((edge.source, -space, -time));
+        }
+
+        // every loop around time passes through the shortest slice
+        let lengths = length_profile.lengths();
+        let shortest_slice = (0..lengths.len())
+            .min_by_key(|&t| lengths[t])
+            .expect("Somehow the length profile is empty");
+        let mut time_loop = (usize::MAX, 0);
+        for vertex in (0..graph.nodes().len()).filter(|&v| graph.nodes()[v].time == shortest_slice)
+        {
+            if let Some(found) = shortest_loop(&adjacency, vertex, Direction::Time, time_loop.0) {
+                time_loop = found;
+            }
+        }
+
+        // every loop around space crosses from the end of a slice to its start somewhere,
+        // and the shortest slice is such a loop itself
+        let mut space_loop = lengths[shortest_slice];
+        for (edge, _) in graph
+            .edges()
+            .iter()
+            .zip(&windings)
+            .filter(|(_, &(space, _))| space != 0)
+        {
+            if let Some((length, _)) =
+                shortest_loop(&adjacency, edge.source, Direction::Space, space_loop)
+            {
+                space_loop = length;
+            }
+        }
+
+        Some(json!({
+            "space_loop": space_loop,
+            "time_loop": time_loop.0,
+            "time_loop_winding": time_loop.1,
+            "modulus": time_loop.0 as f64 / space_loop as f64,
+        }))
+    }
+}
+
+/// Breadth-first search in the covering space for the shortest loop through the source winding
+/// once in the given direction, giving its length and its winding around space, if it is shorter
+/// than the limit
+fn shortest_loop(
+    adjacency: &[Vec<(usize, isize, isize)>],
+    source: usize,
+    direction: Direction,
+    limit: usize,
+) -> Option<(usize, isize)> {
+    // around time the winding around space is free, so it is not part of the state
+    let state = |vertex: usize, space: isize, time: isize| match direction {
+        Direction::Space => (vertex, space, time),
+        Direction::Time => (vertex, 0, time),
+    };
+    let target = match direction {
+        Direction::Space => (source, 1, 0),
+        Direction::Time => (source, 0, 1),
+    };
+
+    let mut seen = HashSet::new();
+    seen.insert((source, 0, 0));
+    let mut front = vec![(source, 0, 0)];
+    let mut distance = 0;
+    while !front.is_empty() && distance + 1 < limit {
+        distance += 1;
+        let mut next = Vec::new();
+        for &(vertex, space, time) in &front {
+            for &(nbr, d_space, d_time) in &adjacency[vertex] {
+                let (space, time) = (space + d_space, time + d_time);
+                let key = state(nbr, space, time);
+                if key == target {
+                    return Some((distance, space));
+                }
+                if seen.insert(key) {
+                    next.push((nbr, space, time));
+                }
+            }
+        }
+        front = next;
+    }
+    None
+}
@@ -0,0 +1,105 @@
+use super::{Observable, Settings};
+use crate::graph;
+use crate::universe::{LengthProfile, Universe};
+use serde_json::{json, Value};
+
+/// The normalized average sphere distance of flat two-dimensional space in the continuum
+const FLAT_SPHERE_DISTANCE: f64 = 1.5746;
+
+/// The quantum Ricci curvature from the average sphere distance on the vertex graph: for two
+/// vertices p and p' at distance delta, the average distance between the points on the spheres
+/// of radius delta around them, divided by delta. The curvature K follows from
+/// d / delta = c (1 - K), where c is the value for flat space. Distances that were never sampled
+/// get null averages, and distances sampled fewer than two times null errors.
+pub struct Ricci {
+    max_distance: usize,
+    counts: Vec<usize>,
+    sums: Vec<f64>,
+    squares: Vec<f64>,
+}
+
+impl Ricci {
+    pub fn new(settings: &Settings) -> Self {
+        Ricci {
+            max_distance: settings.max_distance,
+            counts: vec![0; settings.max_distance],
+            sums: vec![0.0; settings.max_distance],
+            squares: vec![0.0; settings.max_distance],
+        }
+    }
+}
+
+impl Observable for Ricci {
+    fn measure(
+        &mut self,
+        universe: &Universe,
+        _length_profile: &LengthProfile,
+        origin: usize,
+    ) -> Option<Value> {
+        let adjacency = universe.vertex_graph(origin).adjacency();
+        let vertex_count = adjacency.len();
+
+        for delta in 1..=self.max_distance {
+            // pick a random pair of vertices at distance delta
+            let p = fastrand::usize(..vertex_count);
+            let from_p = graph::distances(&adjacency, p, delta);
+            let sphere_p = sphere(&from_p, delta);
+            if sphere_p.is_empty() {
+                continue;
+            }
+            let p_prime = sphere_p[fastrand::usize(..sphere_p.len())];
+            let sphere_p_prime = sphere(&graph::distances(&adjacency, p_prime, delta), delta);
+
+            // search from the smallest sphere, the spheres are at most 3 delta apart
+            let (sources, targets) = match sphere_p.len() <= sphere_p_prime.len() {
+                true => (&sphere_p, &sphere_p_prime),
+                false => (&sphere_p_prime, &sphere_p),
+            };
+            let mut total = 0;
+            for &q in sources {
+                let from_q = graph::distances(&adjacency, q, 3 * delta);
+                total += targets.iter().map(|&r| from_q[r]).sum::<usize>();
+            }
+            let average = total as f64 / (sphere_p.len() * sphere_p_prime.len()) as f64;
+            let normalized = average / delta as f64;
+
+            self.counts[delta - 1] += 1;
+            self.sums[delta - 1] += normalized;
+            self.squares[delta - 1] += normalized * normalized;
+        }
+        None
+    }
+
+    fn finish(&self) -> Option<Value> {
+        let mut average = Vec::with_capacity(self.max_distance);
+        let mut error = Vec::with_capacity(self.max_distance);
+        for delta in 0..self.max_distance {
+            let n = self.counts[delta] as f64;
+            let mean = self.sums[delta] / n;
+            let variance = self.squares[delta] / n - mean * mean;
+            average.push((self.counts[delta] > 0).then_some(mean));
+            error.push((self.counts[delta] > 1).then(|| (variance.max(0.0) / (n - 1.0)).sqrt()));
+        }
+
+        Some(json!({
+            "delta": (1..=self.max_distance).collect::<Vec<_>>(),
+            "counts": self.counts,
+            "average_sphere_distance": average,
+            "average_sphere_distance_error": error,
+            "curvature": average
+                .iter()
+                .map(|d| d.map(|d| 1.0 - d / FLAT_SPHERE_DISTANCE))
+                .collect::<Vec<_>>(),
+            "curvature_error": error
+                .iter()
+                .map(|e| e.map(|e| e / FLAT_SPHERE_DISTANCE))
+                .collect::<Vec<_>>(),
+        }))
+    }
+}
+
+fn sphere(distances: &[usize], radius: usize) -> Vec<usize> {
+    (0..distances.len())
+        .filter(|&vertex| distances[vertex] == radius)
+        .collect()
+}
@@ -0,0 +1,93 @@
+use super::Observable;
+use crate::linalg;
+use crate::universe::{LengthProfile, Universe};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// The number of leading eigenvalues of the transfer matrix to report
+const EIGENVALUE_COUNT: usize = 10;
+
+/// Histogram of the lengths (l_t, l_t+1) of subsequent slices, from which the transfer matrix
+/// is estimated. The transition matrix P(l' | l) is similar to T(l, l') / lambda_0 (up to the
+/// ground state wave function), so its eigenvalues are the ratios lambda_i / lambda_0 of the
+/// eigenvalues of the transfer matrix, and -ln(lambda_i / lambda_0) are the energy gaps.
+pub struct TransferMatrix {
+    histogram: BTreeMap<(usize, usize), usize>,
+}
+
+impl TransferMatrix {
+    pub fn new() -> Self {
+        TransferMatrix {
+            histogram: BTreeMap::new(),
+        }
+    }
+}
+
+impl Observable for TransferMatrix {
+    fn measure(
+        &mut self,
+        _universe: &Universe,
+        length_profile: &LengthProfile,
+        _origin: usize,
+    ) -> Option<Value> {
+        let lengths = length_profile.lengths();
+        let t_max = lengths.len();
+        for t in 0..t_max {
+            *self
+                .histogram
+                .entry((lengths[t], lengths[(t + 1) % t_max]))
+                .or_insert(0) += 1;
+        }
+        None
+    }
+
+    fn finish(&self) -> Option<Value> {
+        if self.histogram.is_empty() {
+            return None;
+        }
+
+        // index the observed lengths
+        let mut lengths: Vec<usize> = self.histogram.keys().flat_map(|&(l, m)| [l, m]).collect();
+        lengths.sort_unstable();
+        lengths.dedup();
+        let n = lengths.len();
+        let index = |l: usize| lengths.binary_search(&l).unwrap();
+
+        // symmetrize the histogram, which is allowed by time reflection symmetry,
+        // and normalize it as D^-1/2 H D^-1/2 to get a symmetric matrix similar to P(l' | l)
+        let mut counts = vec![0.0; n * n];
+        for (&(l, m), &count) in &self.histogram {
+            counts[index(l) * n + index(m)] += count as f64;
+            counts[index(m) * n + index(l)] += count as f64;
+        }
+        let row_sums: Vec<f64> = (0..n)
+            .map(|i| counts[i * n..(i + 1) * n].iter().sum())
+            .collect();
+        let symmetric: Vec<f64> = (0..n * n)
+            .map(|k| counts[k] / (row_sums[k / n] * row_sums[k % n]).sqrt())
+            .collect();
+
+        let eigenvalues: Vec<f64> = linalg::symmetric_eigenvalues(&symmetric, n)
+            .into_iter()
+            .take(EIGENVALUE_COUNT)
+            .collect();
+        let energy_gaps: Vec<f64> = eigenvalues
+            .iter()
+            .map(|eigenvalue| -(eigenvalue / eigenvalues[0]).ln())
+            .collect();
+
+        Some(json!({
+            "histogram": self
+                .histogram
+                .iter()
+                .map(|(&(l, m), &count)| [l, m, count])
+                .collect::<Vec<_>>(),
+            "lengths": lengths,
+            "transition": (0..n)
+                .map(|i| (0..n).map(|j| counts[i * n + j] / row_sums[i]).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            "eigenvalues": eigenvalues,
+            "energy_gaps": energy_gaps,
+        }))
+    }
+}
@@ -0,0 +1,212 @@
+use crate::universe::{Orientation, Universe};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Read a universe from either a slab orientation file, or a dual graph exported as GraphML
+pub fn read_universe(path: &str) -> io::Result<Universe> {
+    let contents = std::fs::read_to_string(path)?;
+    let universe = if path.ends_with(".graphml") {
+        parse_dual_graphml(&contents)
+    } else {
+        parse_slab_orientations(&contents)
+    };
+    universe.map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid triangulation in {}: {}", path, err),
+        )
+    })
+}
+
+/// Write a universe as a slab orientation file, one line of up (U) and down (D) triangles per slab,
+/// preceded by the twist with which the last slab is glued to the first one
+pub fn write_universe(universe: &Universe, path: &str) -> io::Result<()> {
+    let (slabs, twist) = universe.slab_orientations(0);
+
+    let mut output = BufWriter::new(File::create(path)?);
+    writeln!(output, "twist {}", twist)?;
+    for slab in slabs {
+        let line: String = slab
+            .iter()
+            .map(|orientation| match orientation {
+                Orientation::Up => 'U',
+                Orientation::Down => 'D',
+            })
+            .collect();
+        writeln!(output, "{}", line)?;
+    }
+    output.flush()
+}
+
+fn parse_slab_orientations(contents: &str) -> Result<Universe, String> {
+    let mut twist = 0;
+    let mut slabs = Vec::new();
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("twist") {
+            twist = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("could not read twist from '{}'", line))?;
+            continue;
+        }
+        let slab = line
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != ',')
+            .map(|c| match c {
+                'U' | 'u' => Ok(Orientation::Up),
+                'D' | 'd' => Ok(Orientation::Down),
+                _ => Err(format!(
+                    "unknown orientation '{}' in slab {}",
+                    c,
+                    slabs.len()
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        slabs.push(slab);
+    }
+    Universe::from_slabs(&slabs, twist)
+}
+
+fn parse_dual_graphml(contents: &str) -> Result<Universe, String> {
+    // A minimal GraphML reader, only looking at the tags used by the dual graph export
+    enum Element {
+        Node(usize),
+        Edge(usize, usize),
+        Other,
+    }
+
+    let mut key_names: HashMap<String, String> = HashMap::new();
+    let mut orientations: HashMap<usize, Orientation> = HashMap::new();
+    let mut right: HashMap<usize, usize> = HashMap::new();
+    let mut time: HashMap<usize, usize> = HashMap::new();
+    let mut element = Element::Other;
+
+    let mut rest = contents;
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>').ok_or("unclosed tag")?;
+        let tag = rest[start + 1..end].trim_end_matches('/');
+        rest = &rest[end + 1..];
+        let text = rest[..rest.find('<').unwrap_or(rest.len())].trim();
+
+        match tag.split_whitespace().next().unwrap_or("") {
+            "key" => {
+                key_names.insert(attribute(tag, "id")?, attribute(tag, "attr.name")?);
+            }
+            "node" => element = Element::Node(parse_id(&attribute(tag, "id")?)?),
+            "edge" => {
+                element = Element::Edge(
+                    parse_id(&attribute(tag, "source")?)?,
+                    parse_id(&attribute(tag, "target")?)?,
+                )
+            }
+            "data" => {
+                let key = attribute(tag, "key")?;
+                let name = key_names.get(&key).map(String::as_str).unwrap_or(&key);
+                match (&element, name) {
+                    (Element::Node(id), "kind") => {
+                        let orientation = match text {
+                            "up" => Orientation::Up,
+                            "down" => Orientation::Down,
+                            _ => return Err(format!("node {} is not an up or down triangle", id)),
+                        };
+                        orientations.insert(*id, orientation);
+                    }
+                    (Element::Edge(source, target), "type") => match text {
+                        "right" => {
+                            right.insert(*source, *target);
+                        }
+                        "time" => {
+                            time.insert(*source, *target);
+                            time.insert(*target, *source);
+                        }
+                        _ => return Err(format!("unknown edge type '{}'", text)),
+                    },
+                    _ => (),
+                }
+            }
+            "/node" | "/edge" => element = Element::Other,
+            _ => (),
+        }
+    }
+
+    let triangle_count = orientations.len();
+    let lookup = |map: &HashMap<usize, usize>, label: usize, what: &str| {
+        map.get(&label)
+            .copied()
+            .ok_or(format!("triangle {} has no {} neighbour", label, what))
+    };
+    let orientations = (0..triangle_count)
+        .map(|label| {
+            orientations
+                .get(&label)
+                .copied()
+                .ok_or(format!("triangle {} is missing", label))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let right = (0..triangle_count)
+        .map(|label| lookup(&right, label, "right"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let time = (0..triangle_count)
+        .map(|label| lookup(&time, label, "time"))
+        .collect::<Result<Vec<_>, _>>()?;
+    Universe::from_links(orientations, right, time)
+}
+
+fn attribute(tag: &str, name: &str) -> Result<String, String> {
+    let pattern = format!(" {}=\"", name);
+    let start = tag
+        .find(&pattern)
+        .ok_or(format!("missing attribute '{}' in <{}>", name, tag))?
+        + pattern.len();
+    let length = tag[start..]
+        .find('"')
+        .ok_or(format!("unclosed attribute '{}' in <{}>", name, tag))?;
+    Ok(tag[start..start + length].to_string())
+}
+
+fn parse_id(id: &str) -> Result<usize, String> {
+    id.trim_start_matches('n')
+        .parse()
+        .map_err(|_| format!("node id '{}' is not a triangle label", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Moves;
+    use crate::universe::LengthDistribution;
+
+    #[test]
+    fn slab_orientations_round_trip() {
+        let mut universe = Universe::random(6, 5, LengthDistribution::Multinomial);
+        let mut moves = Moves::from_ratio(0.4, &[]);
+        for _ in 0..1000 {
+            universe.mcmc_step(&mut moves);
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("state_round_trip_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_universe(&universe, path).unwrap();
+        let read = read_universe(path);
+        std::fs::remove_file(path).unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(read.size(), universe.size());
+        assert_eq!(read.slab_orientations(0), universe.slab_orientations(0));
+        assert_eq!(
+            read.length_profile(0).lengths(),
+            universe.length_profile(0).lengths()
+        );
+    }
+
+    #[test]
+    fn reject_without_triangles() {
+        assert!(parse_slab_orientations("twist 0\n").is_err());
+        assert!(parse_slab_orientations("").is_err());
+    }
+}
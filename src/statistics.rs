@@ -0,0 +1,105 @@
+/// The number of initial points to discard from a series to remove its initial transient, using the
+/// marginal standard error rule on batches of five points (MSER-5): the truncation in the first half
+/// of the series minimizing the variance of the remaining points divided by their number squared
+pub fn truncation_point(series: &[f64]) -> usize {
+    let batches: Vec<f64> = series
+        .chunks_exact(5)
+        .map(|batch| batch.iter().sum::<f64>() / 5.0)
+        .collect();
+    let n = batches.len();
+
+    // sums over the remaining batches for every truncation
+    let mut sum = 0.0;
+    let mut squares = 0.0;
+    let mut best = (f64::INFINITY, 0);
+    for d in (0..n).rev() {
+        sum += batches[d];
+        squares += batches[d] * batches[d];
+        let remaining = (n - d) as f64;
+        let standard_error = (squares - sum * sum / remaining) / (remaining * remaining);
+        if d <= n / 2 && standard_error <= best.0 {
+            best = (standard_error, d);
+        }
+    }
+    5 * best.1
+}
+
+/// The integrated autocorrelation time of a series in units of its spacing, being one half plus the
+/// sum of the normalized autocorrelation function, summed up to the first lag W with W >= 6 tau
+/// (automatic windowing after Sokal) to avoid summing mostly noise
+pub fn integrated_autocorrelation_time(series: &[f64]) -> f64 {
+    let n = series.len();
+    let mean = series.iter().sum::<f64>() / n as f64;
+    let deviations: Vec<f64> = series.iter().map(|x| x - mean).collect();
+    let autocovariance = |lag: usize| {
+        (0..n - lag)
+            .map(|i| deviations[i] * deviations[i + lag])
+            .sum::<f64>()
+            / n as f64
+    };
+    let variance = autocovariance(0);
+    if variance == 0.0 {
+        return 0.5;
+    }
+
+    let mut tau = 0.5;
+    for lag in 1..n {
+        tau += autocovariance(lag) / variance;
+        if lag as f64 >= 6.0 * tau {
+            break;
+        }
+    }
+    tau
+}
+
+/// The mean and central moments m2, m3 and m4 of a distribution, with the skewness, kurtosis and
+/// Binder cumulant derived from them; if all values are equal these are 0, 3 and 0 respectively,
+/// as for a normal distribution, instead of dividing by the vanishing variance
+pub struct Moments {
+    pub mean: f64,
+    pub variance: f64,
+    m3: f64,
+    m4: f64,
+}
+
+impl Moments {
+    /// The moments of pairs of a value and how often it occurs
+    pub fn from_weighted(values: impl Iterator<Item = (f64, f64)> + Clone) -> Self {
+        let total = values.clone().map(|(_, weight)| weight).sum::<f64>();
+        let mean = values.clone().map(|(x, weight)| weight * x).sum::<f64>() / total;
+        let central = |order: i32| {
+            values
+                .clone()
+                .map(|(x, weight)| weight * (x - mean).powi(order))
+                .sum::<f64>()
+                / total
+        };
+        Moments {
+            mean,
+            variance: central(2),
+            m3: central(3),
+            m4: central(4),
+        }
+    }
+
+    /// The skewness m3 / m2^(3/2)
+    pub fn skewness(&self) -> f64 {
+        match self.variance {
+            0.0 => 0.0,
+            variance => self.m3 / variance.powf(1.5),
+        }
+    }
+
+    /// The kurtosis m4 / m2^2, which is 3 for a normal distribution
+    pub fn kurtosis(&self) -> f64 {
+        match self.variance {
+            0.0 => 3.0,
+            variance => self.m4 / variance.powi(2),
+        }
+    }
+
+    /// The Binder cumulant 1 - m4 / (3 m2^2), vanishing for a normal distribution
+    pub fn binder_cumulant(&self) -> f64 {
+        1.0 - self.kurtosis() / 3.0
+    }
+}
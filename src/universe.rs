@@ -1,12 +1,24 @@
+use crate::graph::{Edge, Graph, Node};
+use crate::moves::Moves;
+use crate::statistics::Moments;
 use std::collections::HashSet;
 use std::f32::consts::TAU;
 use std::fmt;
 use std::ops::Index;
+use std::str::FromStr;
 
 #[derive(Clone, Debug)]
 pub struct Universe {
     triangles: Vec<Triangle>,
     order_four: HashSet<usize>, // keeps a list of order 4 vertices, labelled by the top-left triangle
+    flippable: LabelSet,        // keeps a list of flippable pairs, labelled by the left triangle
+}
+
+/// A set of triangle labels that can be sampled uniformly in constant time
+#[derive(Clone, Debug)]
+struct LabelSet {
+    labels: Vec<usize>,
+    positions: Vec<Option<usize>>,
 }
 
 #[derive(Clone, Debug)]
@@ -18,14 +30,60 @@ struct Triangle {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-enum Orientation {
+pub enum Orientation {
     Up,
     Down,
 }
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize)]
 pub struct LengthProfile(Vec<usize>);
 
+/// The surface the triangulation is drawn on when writing the mesh
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Embedding {
+    Torus,
+    Cylinder,
+    Strip,
+}
+
+/// A statistic of the slice lengths of a length profile
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Statistic {
+    Mean,
+    Variance,
+    Stdev,
+    Skewness,
+    Kurtosis,
+    Min,
+    Max,
+    Binder,
+}
+
+/// The triangulation a run starts from when no initial state is given
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Start {
+    /// every slice of the same length
+    Flat,
+    /// random slice lengths and uniformly random slabs
+    Random,
+    /// all slices of length one, except for a single long slice
+    Pinched,
+}
+
+/// The distribution of the slice lengths of a random start, at fixed total volume
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthDistribution {
+    /// every vertex beyond the first of every slice put in a uniformly random slice
+    Multinomial,
+    /// uniform over all length profiles
+    Uniform,
+}
+
+#[derive(Clone, Copy)]
 struct Vertex(usize, usize);
 #[derive(Clone, Copy)]
 pub struct VertexPosition(f32, f32, f32);
@@ -58,37 +116,356 @@ impl Universe {
             }
         }
         let order_four = HashSet::new();
-        Universe {
+        let mut universe = Universe {
+            flippable: LabelSet::new(triangles.len()),
             triangles,
             order_four,
+        };
+        for label in 0..universe.triangles.len() {
+            universe.update_flippable(label);
         }
+        universe
     }
 
-    pub fn mcmc_step(&mut self, move_ratio: f32) {
-        if self.order_four.is_empty() || (fastrand::f32() > move_ratio) {
-            let left = self.sample_uniform();
-            // only flip when possible, do nothing otherwise
-            // this is to ensure detailed balance
-            if self.is_flippable(left) {
-                self.triangle_flip(left);
+    pub fn from_slabs(slabs: &[Vec<Orientation>], twist: usize) -> Result<Self, String> {
+        // Build a universe from the orientations of the triangles in every slab,
+        // the j-th down triangle of a slab is glued to the j-th up triangle of the next slab,
+        // except for the last slab which is glued to the first one shifted by the twist
+        let t_max = slabs.len();
+        if t_max == 0 {
+            return Err("there are no slabs".to_string());
+        }
+
+        let mut ups = Vec::with_capacity(t_max);
+        let mut downs = Vec::with_capacity(t_max);
+        let mut orientations = Vec::new();
+        let mut right = Vec::new();
+        for (t, slab) in slabs.iter().enumerate() {
+            let offset = orientations.len();
+            let (slab_ups, slab_downs): (Vec<usize>, Vec<usize>) = (offset..offset + slab.len())
+                .partition(|&label| slab[label - offset] == Orientation::Up);
+            if slab_ups.is_empty() || slab_downs.is_empty() {
+                return Err(format!(
+                    "slab {} does not contain both up and down triangles",
+                    t
+                ));
             }
-        } else {
-            let shard_up = self.sample_shard();
-            let dest_up = self.sample_dest(shard_up);
-            self.shard_move(shard_up, dest_up);
+            ups.push(slab_ups);
+            downs.push(slab_downs);
+            orientations.extend_from_slice(slab);
+            right.extend((0..slab.len()).map(|i| offset + (i + 1) % slab.len()));
+        }
+
+        let mut time = vec![0; orientations.len()];
+        for (t, slab_downs) in downs.iter().enumerate() {
+            let next = (t + 1) % t_max;
+            if slab_downs.len() != ups[next].len() {
+                return Err(format!(
+                    "slab {} has {} down triangles, but slab {} has {} up triangles",
+                    t,
+                    slab_downs.len(),
+                    next,
+                    ups[next].len()
+                ));
+            }
+            let shift = if next == 0 { twist } else { 0 };
+            for (j, &down) in slab_downs.iter().enumerate() {
+                let up = ups[next][(j + shift) % ups[next].len()];
+                time[down] = up;
+                time[up] = down;
+            }
+        }
+        Self::from_links(orientations, right, time)
+    }
+
+    pub fn from_profile(lengths: &[usize]) -> Result<Self, String> {
+        // Build a universe with the given slice lengths, spreading the up and down triangles of
+        // every slab as evenly as possible, starting with an up triangle
+        let slabs = Self::slab_lengths(lengths)?
+            .map(|(ups, downs)| {
+                let n = ups + downs;
+                (0..n)
+                    .map(
+                        |i| match ((i + 1) * ups).div_ceil(n) > (i * ups).div_ceil(n) {
+                            true => Orientation::Up,
+                            false => Orientation::Down,
+                        },
+                    )
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+        Self::from_slabs(&slabs, 0)
+    }
+
+    pub fn shuffled(lengths: &[usize]) -> Result<Self, String> {
+        // Build a universe with the given slice lengths, drawing the order of the up and down
+        // triangles of every slab (seen from the vertex the next slab starts from) and the twist
+        // uniformly at random, giving every triangulation with these lengths equal probability
+        let slabs = Self::slab_lengths(lengths)?
+            .map(|(ups, downs)| {
+                let mut rest: Vec<Orientation> = std::iter::repeat_n(Orientation::Up, ups - 1)
+                    .chain(std::iter::repeat_n(Orientation::Down, downs))
+                    .collect();
+                fastrand::shuffle(&mut rest);
+                std::iter::once(Orientation::Up).chain(rest).collect()
+            })
+            .collect::<Vec<_>>();
+        Self::from_slabs(&slabs, fastrand::usize(..lengths[0]))
+    }
+
+    pub fn random(timespan: usize, length: usize, distribution: LengthDistribution) -> Self {
+        // Start with slice lengths drawn from the given distribution, keeping the total volume
+        // of the flat universe, and uniformly random slabs
+        assert!(
+            timespan > 0 && length > 0,
+            "the universe needs at least one slice of length one"
+        );
+        let volume = timespan * length;
+        let lengths = match distribution {
+            LengthDistribution::Multinomial => {
+                let mut lengths = vec![1; timespan];
+                for _ in timespan..volume {
+                    lengths[fastrand::usize(..timespan)] += 1;
+                }
+                lengths
+            }
+            LengthDistribution::Uniform => {
+                // cut the vertices into consecutive slices at timespan - 1 distinct positions
+                let mut positions: Vec<usize> = (1..volume).collect();
+                fastrand::shuffle(&mut positions);
+                let mut cuts = positions[..timespan - 1].to_vec();
+                cuts.sort_unstable();
+                cuts.insert(0, 0);
+                cuts.push(volume);
+                cuts.windows(2).map(|cut| cut[1] - cut[0]).collect()
+            }
+        };
+        Self::shuffled(&lengths).expect("Somehow the random slice lengths are invalid")
+    }
+
+    pub fn pinched(timespan: usize, length: usize) -> Self {
+        // Start with all but one slice of length one, keeping the total volume of the flat
+        // universe, which is as far from the flat universe as the slice lengths can get; the
+        // long slab next to the short ones is the worst case for building and validating, which
+        // has to stay linear in the volume for the timespans of real runs
+        assert!(
+            timespan > 0 && length > 0,
+            "the universe needs at least one slice of length one"
+        );
+        let mut lengths = vec![1; timespan];
+        lengths[0] = timespan * length - (timespan - 1);
+        Self::from_profile(&lengths).expect("Somehow the pinched slice lengths are invalid")
+    }
+
+    fn slab_lengths(
+        lengths: &[usize],
+    ) -> Result<impl Iterator<Item = (usize, usize)> + '_, String> {
+        // The number of up and down triangles of every slab, being the lengths of the slice
+        // below and above it
+        if lengths.is_empty() {
+            return Err("there are no slices".to_string());
+        }
+        if let Some(t) = lengths.iter().position(|&length| length == 0) {
+            return Err(format!("slice {} has no vertices", t));
+        }
+        Ok((0..lengths.len()).map(|t| (lengths[t], lengths[(t + 1) % lengths.len()])))
+    }
+
+    pub fn from_links(
+        orientations: Vec<Orientation>,
+        right: Vec<usize>,
+        time: Vec<usize>,
+    ) -> Result<Self, String> {
+        // Build a universe from the orientation, right neighbour and time neighbour of every triangle
+        let triangle_count = orientations.len();
+        if right.len() != triangle_count || time.len() != triangle_count {
+            return Err(
+                "every triangle needs an orientation, right and time neighbour".to_string(),
+            );
         }
+        if let Some(&label) = right
+            .iter()
+            .chain(&time)
+            .find(|&&label| label >= triangle_count)
+        {
+            return Err(format!("triangle {} does not exist", label));
+        }
+
+        let mut left = vec![None; triangle_count];
+        for (label, &right_nbr) in right.iter().enumerate() {
+            if left[right_nbr].replace(label).is_some() {
+                return Err(format!("triangle {} has two left neighbours", right_nbr));
+            }
+        }
+
+        let triangles = orientations
+            .into_iter()
+            .zip(left)
+            .zip(right.into_iter().zip(time))
+            .map(|((orientation, left), (right, time))| Triangle {
+                orientation,
+                time,
+                left: left.expect("every triangle has a left neighbour"),
+                right,
+            })
+            .collect();
+        let mut universe = Universe {
+            triangles,
+            order_four: HashSet::new(),
+            flippable: LabelSet::new(triangle_count),
+        };
+        universe.validate()?;
+
+        // reconstruct the order four vertices and flippable pairs
+        for label in 0..triangle_count {
+            universe.add_if_order_four(label);
+            universe.update_flippable(label);
+        }
+        Ok(universe)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        // Check that the triangles glue together into a single toroidal triangulation
+        let triangles = &self.triangles;
+        if triangles.is_empty() {
+            return Err("there are no triangles".to_string());
+        }
+        for (label, triangle) in triangles.iter().enumerate() {
+            if triangles[triangle.right].left != label || triangles[triangle.left].right != label {
+                return Err(format!(
+                    "left and right neighbours of triangle {} do not match",
+                    label
+                ));
+            }
+            let time_nbr = &triangles[triangle.time];
+            if time_nbr.time != label || time_nbr.orientation == triangle.orientation {
+                return Err(format!(
+                    "time neighbours of triangle {} do not match",
+                    label
+                ));
+            }
+        }
+
+        // every slab needs both orientations, checked walking through every slab once
+        let mut visited = vec![false; triangles.len()];
+        for label in 0..triangles.len() {
+            if visited[label] {
+                continue;
+            }
+            let (mut has_up, mut has_down) = (false, false);
+            let mut walker = label;
+            loop {
+                visited[walker] = true;
+                match triangles[walker].orientation {
+                    Orientation::Up => has_up = true,
+                    Orientation::Down => has_down = true,
+                }
+                walker = triangles[walker].right;
+                if walker == label {
+                    break;
+                }
+            }
+            if !(has_up && has_down) {
+                return Err(format!(
+                    "the slab of triangle {} does not contain both up and down triangles",
+                    label
+                ));
+            }
+        }
+
+        // the time neighbours of two subsequent down triangles need to be subsequent up
+        // triangles to preserve the ordering
+        for (label, triangle) in triangles.iter().enumerate() {
+            if triangle.orientation == Orientation::Down {
+                let mut next_down = triangle.right;
+                while triangles[next_down].orientation != Orientation::Down {
+                    next_down = triangles[next_down].right;
+                }
+                let mut next_up = triangles[triangle.time].right;
+                while triangles[next_up].orientation != Orientation::Up {
+                    next_up = triangles[next_up].right;
+                }
+                if triangles[next_down].time != next_up {
+                    return Err(format!(
+                        "time neighbours of triangle {} and {} are not adjacent",
+                        label, next_down
+                    ));
+                }
+            }
+        }
+
+        // all triangles need to be reached by walking through the slabs
+        let covered = self.slabs(0).iter().map(|slab| slab.len()).sum::<usize>();
+        if covered != triangles.len() {
+            return Err(format!(
+                "only {} out of {} triangles are connected",
+                covered,
+                triangles.len()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn slab_orientations(&self, origin: usize) -> (Vec<Vec<Orientation>>, usize) {
+        // The inverse of from_slabs, giving the orientations in every slab and the twist
+        let slabs = self.slabs(origin);
+        let twist = self.twist(&slabs);
+
+        let orientations = slabs
+            .iter()
+            .map(|slab| {
+                slab.iter()
+                    .map(|&label| self.triangles[label].orientation)
+                    .collect()
+            })
+            .collect();
+        (orientations, twist)
+    }
+
+    fn twist(&self, slabs: &[Vec<usize>]) -> usize {
+        // The position in the first slice of the vertex the last slab glues back onto,
+        // counted in up triangles from the start of the first slab
+        let first_ups: Vec<usize> = slabs[0]
+            .iter()
+            .copied()
+            .filter(|&label| self.triangles[label].orientation == Orientation::Up)
+            .collect();
+        let last_down = *slabs[slabs.len() - 1]
+            .iter()
+            .find(|&&label| self.triangles[label].orientation == Orientation::Down)
+            .expect("Somehow there was no down-triangle in timeslice");
+        first_ups
+            .iter()
+            .position(|&label| label == self.triangles[last_down].time)
+            .expect("Somehow the last slab is not glued to the first")
+    }
+
+    pub fn size(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn timespan(&self) -> usize {
+        self.length_profile(0).len()
+    }
+
+    pub fn mcmc_step(&mut self, moves: &mut Moves) {
+        moves.step(self);
+    }
+
+    pub fn rejection_free_step(&mut self, moves: &mut Moves, max_steps: usize) -> usize {
+        moves.step_rejection_free(self, max_steps)
     }
 
-    fn sample_uniform(&self) -> usize {
+    pub(crate) fn sample_uniform(&self) -> usize {
         fastrand::usize(..self.triangles.len())
     }
 
-    fn is_flippable(&self, left: usize) -> bool {
+    pub(crate) fn is_flippable(&self, left: usize) -> bool {
         let right = self.triangles[left].right;
         self.triangles[left].orientation != self.triangles[right].orientation
     }
 
-    fn sample_dest(&self, shard: usize) -> usize {
+    pub(crate) fn sample_dest(&self, shard: usize) -> usize {
         loop {
             let index = fastrand::usize(..self.triangles.len());
             let dest = match self.triangles[index].orientation {
@@ -101,12 +478,45 @@ impl Universe {
         }
     }
 
-    fn sample_shard(&self) -> usize {
+    pub(crate) fn flippable_count(&self) -> usize {
+        self.flippable.len()
+    }
+
+    pub(crate) fn sample_flippable(&self) -> usize {
+        self.flippable.sample()
+    }
+
+    pub(crate) fn has_order_four(&self) -> bool {
+        !self.order_four.is_empty()
+    }
+
+    /// Whether moving the shard to the destination would put it back where it is
+    pub(crate) fn is_shard_position(&self, shard_up: usize, dest_up: usize) -> bool {
+        dest_up == self.triangles[shard_up].left
+    }
+
+    pub(crate) fn sample_shard(&self) -> usize {
         let index = fastrand::usize(..self.order_four.len());
         self.triangles[*self.order_four.iter().nth(index).unwrap()].right
     }
 
-    fn triangle_flip(&mut self, left: usize) {
+    /// The run of shards starting at the given one, if the given number of shards to its right
+    /// (including itself) all sit at order four vertices and do not make up the whole slab
+    pub(crate) fn shard_run(&self, shard_up: usize, count: usize) -> Option<Vec<usize>> {
+        let start = self.triangles[shard_up].left;
+        let mut run = vec![shard_up];
+        while run.len() < count {
+            let previous = run[run.len() - 1];
+            let next = self.triangles[previous].right;
+            if !self.order_four.contains(&previous) || next == start {
+                return None;
+            }
+            run.push(next);
+        }
+        Some(run)
+    }
+
+    pub(crate) fn triangle_flip(&mut self, left: usize) {
         // identify the relevant triangles
         let right = self.triangles[left].right;
         let left_nbr = self.triangles[left].time;
@@ -114,6 +524,9 @@ impl Universe {
 
         // flip the orientations
         self.swap_orientation(left, right);
+        for label in [self.triangles[left].left, left, right] {
+            self.update_flippable(label);
+        }
 
         // reassign neighbours
         self.triangles[left_nbr].time = right;
@@ -146,7 +559,7 @@ impl Universe {
         self.triangles[right].orientation = left_orientation;
     }
 
-    fn shard_move(&mut self, shard_up: usize, dest_up: usize) {
+    pub(crate) fn shard_move(&mut self, shard_up: usize, dest_up: usize) {
         // If move is to original position, do nothing
         let shard_nbr_left_up = self.triangles[shard_up].left;
         if dest_up == shard_nbr_left_up {
@@ -184,6 +597,18 @@ impl Universe {
         self.triangles[shard_down].right = dest_nbr_down;
         self.triangles[shard_down].left = dest_down;
 
+        // update flippable, which only changes for triangles that got a new right neighbour
+        for label in [
+            shard_nbr_left_up,
+            shard_nbr_left_down,
+            dest_up,
+            dest_down,
+            shard_up,
+            shard_down,
+        ] {
+            self.update_flippable(label);
+        }
+
         // update order_four
         let dest_order4 = !self.order_four.insert(dest_up); // Add dest_up as order 4, and check if it already was
         let shard_order4 = if dest_order4 {
@@ -198,6 +623,126 @@ impl Universe {
         }
     }
 
+    pub(crate) fn slab_heatbath(&mut self, label: usize) {
+        // Redraw the slab of the given triangle from all slabs fitting between the slices below
+        // and above it: the orientations of the triangles in the slab are shuffled, and the up
+        // (down) triangles are glued to the same edges of the slice below (above) in the same
+        // order, but starting from a uniformly random one
+        let mut slab = vec![label];
+        let mut walker = self.triangles[label].right;
+        while walker != label {
+            slab.push(walker);
+            walker = self.triangles[walker].right;
+        }
+
+        // the time neighbours of the up and down triangles, in the order of the slab
+        let (below, above): (Vec<usize>, Vec<usize>) = slab
+            .iter()
+            .map(|&label| self.triangles[label].time)
+            .partition(|&nbr| self.triangles[nbr].orientation == Orientation::Down);
+
+        let mut orientations: Vec<Orientation> = std::iter::repeat_n(Orientation::Up, below.len())
+            .chain(std::iter::repeat_n(Orientation::Down, above.len()))
+            .collect();
+        fastrand::shuffle(&mut orientations);
+        let mut next_below = fastrand::usize(..below.len());
+        let mut next_above = fastrand::usize(..above.len());
+        for (&label, orientation) in slab.iter().zip(orientations) {
+            let nbr = match orientation {
+                Orientation::Up => {
+                    next_below = (next_below + 1) % below.len();
+                    below[next_below]
+                }
+                Orientation::Down => {
+                    next_above = (next_above + 1) % above.len();
+                    above[next_above]
+                }
+            };
+            self.triangles[label].orientation = orientation;
+            self.triangles[label].time = nbr;
+            self.triangles[nbr].time = label;
+        }
+
+        // update flippable, which can change for every triangle of this slab, and order_four,
+        // which can change for the up triangles of this slab and the one above
+        for &label in &slab {
+            self.update_flippable(label);
+        }
+        for &label in slab.iter().chain(&above) {
+            self.order_four.remove(&label);
+        }
+        for &label in slab.iter().chain(&above) {
+            self.add_if_order_four(label);
+        }
+    }
+
+    pub(crate) fn shard_run_move(&mut self, run: &[usize], dest_up: usize) {
+        // Move a run of adjacent shards to the right of the destination, like shard_move
+        // If move is to original position, do nothing
+        let first_up = run[0];
+        let last_up = run[run.len() - 1];
+        let run_nbr_left_up = self.triangles[first_up].left;
+        if dest_up == run_nbr_left_up {
+            return;
+        }
+
+        // identify the down triangles at the ends of the run and of the destination
+        let first_down = self.triangles[first_up].time;
+        let last_down = self.triangles[last_up].time;
+        let dest_down = self.triangles[dest_up].time;
+
+        // identify the run's neighbours
+        let run_nbr_right_up = self.triangles[last_up].right;
+        let run_nbr_left_down = self.triangles[first_down].left;
+        let run_nbr_right_down = self.triangles[last_down].right;
+
+        // reassign neighbours around original location (close the gap left behind by the run)
+        self.triangles[run_nbr_left_up].right = run_nbr_right_up;
+        self.triangles[run_nbr_right_up].left = run_nbr_left_up;
+        self.triangles[run_nbr_left_down].right = run_nbr_right_down;
+        self.triangles[run_nbr_right_down].left = run_nbr_left_down;
+
+        // identify the neighbours near the destination
+        let dest_nbr_up = self.triangles[dest_up].right;
+        let dest_nbr_down = self.triangles[dest_down].right;
+
+        // reassign neighbours around new run location (insert the run in its new location)
+        self.triangles[dest_up].right = first_up;
+        self.triangles[first_up].left = dest_up;
+        self.triangles[last_up].right = dest_nbr_up;
+        self.triangles[dest_nbr_up].left = last_up;
+        self.triangles[dest_down].right = first_down;
+        self.triangles[first_down].left = dest_down;
+        self.triangles[last_down].right = dest_nbr_down;
+        self.triangles[dest_nbr_down].left = last_down;
+
+        // update flippable and order_four, which only change for triangles that got a new right
+        // neighbour (directly or below)
+        for label in [
+            run_nbr_left_up,
+            run_nbr_left_down,
+            dest_up,
+            dest_down,
+            last_up,
+            last_down,
+        ] {
+            self.update_flippable(label);
+        }
+        for &label in [run_nbr_left_up, dest_up].iter().chain(run) {
+            self.order_four.remove(&label);
+        }
+        for &label in [run_nbr_left_up, dest_up].iter().chain(run) {
+            self.add_if_order_four(label);
+        }
+    }
+
+    fn update_flippable(&mut self, label: usize) {
+        match self.is_flippable(label) {
+            true => self.flippable.insert(label),
+            false => self.flippable.remove(label),
+        }
+    }
+
     fn add_if_order_four(&mut self, label: usize) {
         if self.is_order_four_at(label) {
             self.order_four.insert(label);
@@ -260,145 +805,274 @@ impl Universe {
         }
     }
 
-    fn triangle_vertices(&self, origin: usize) -> Vec<(Vertex, Vertex, Vertex)> {
-        // Walk through the slices similarly to find the length profile but now returning triangulation
-        let triangles = &self.triangles;
+    fn slabs(&self, origin: usize) -> Vec<Vec<usize>> {
+        // Collect the labels of the triangles in each slab, walking from left to right
+        // through the slabs in the same order as for the length profile
+        let t_max = self.length_profile(origin).len();
+        let mut slabs = Vec::with_capacity(t_max);
 
-        // Determine the length profile
-        let lengths = self.length_profile(origin);
-        let t_max = lengths.len();
-
-        let mut triangle_vertices: Vec<(Vertex, Vertex, Vertex)> =
-            Vec::with_capacity(self.triangles.len() / 2);
-        let mut marker = origin; // Marks the current triangle
-                                 // Loop over all timeslices
-        for t in 0..t_max {
-            let mut lower_index: usize = 0;
-            let mut upper_index: usize = 0;
+        let mut marker = origin;
+        for _ in 0..t_max {
+            let mut slab = Vec::new();
             let mut next_origin: Option<usize> = None;
-            for _ in 0..(lengths[t] + lengths[(t + 1) % t_max]) {
-                // Loop over all triangles
-                match triangles[marker].orientation {
-                    Orientation::Up => {
-                        let right_index = (lower_index + 1) % lengths[t]; // Determine next index
-                        // Add triangle with orientation based on right-handedness
-                        triangle_vertices.push((
-                            Vertex(t, lower_index),
-                            Vertex(t + 1, upper_index),
-                            Vertex(t, right_index),
-                        ));
-                        lower_index = right_index // Update index
+            let mut slab_walker = marker;
+            loop {
+                if next_origin.is_none()
+                    && self.triangles[slab_walker].orientation == Orientation::Down
+                {
+                    next_origin = Some(self.triangles[slab_walker].time);
+                }
+                slab.push(slab_walker);
+                slab_walker = self.triangles[slab_walker].right;
+                if slab_walker == marker {
+                    break;
+                }
+            }
+            slabs.push(slab);
+            marker = next_origin.expect("Somehow there was no down-triangle in timeslice");
+        }
+        slabs
+    }
+
+    pub fn slab_origin(&self, origin: usize, t: usize) -> usize {
+        // The triangle at which the walk from the origin enters slab t
+        self.slabs(origin)[t][0]
+    }
+
+    pub fn in_same_slab(&self, label: usize, other: usize) -> bool {
+        // Walk through the slab of the first triangle looking for the other one
+        let mut walker = label;
+        loop {
+            if walker == other {
+                return true;
+            }
+            walker = self.triangles[walker].right;
+            if walker == label {
+                return false;
+            }
+        }
+    }
+
+    fn triangle_corners(&self) -> Vec<[usize; 3]> {
+        // Find the vertices of every triangle, in the same right-handed order as the mesh
+        // each vertex is labelled by the up triangle of which it is the bottom-left corner
+        let triangle_count = self.triangles.len();
+
+        // for every triangle find the first up triangle on its right
+        // and the first down triangle on its left
+        let mut next_up = vec![0; triangle_count];
+        let mut prev_down = vec![0; triangle_count];
+        for (label, triangle) in self.triangles.iter().enumerate() {
+            match triangle.orientation {
+                Orientation::Up => {
+                    let mut walker = triangle.left;
+                    loop {
+                        next_up[walker] = label;
+                        if self.triangles[walker].orientation == Orientation::Up {
+                            break;
+                        }
+                        walker = self.triangles[walker].left;
                     }
-                    Orientation::Down => {
-                        if next_origin.is_none() {
-                            next_origin = Some(triangles[marker].time)
+                }
+                Orientation::Down => {
+                    let mut walker = triangle.right;
+                    loop {
+                        prev_down[walker] = label;
+                        if self.triangles[walker].orientation == Orientation::Down {
+                            break;
                         }
-                        let right_index = (upper_index + 1) % lengths[(t + 1) % t_max]; // Determine next index
-                                                                                                                // Add triangle with orientation based on right-handedness
-                        triangle_vertices.push((
-                            Vertex(t + 1, upper_index),
-                            Vertex(t + 1, right_index),
-                            Vertex(t, lower_index),
-                        ));
-                        upper_index = right_index // Update index
+                        walker = self.triangles[walker].right;
                     }
                 }
-                marker = triangles[marker].right;
             }
-            marker = next_origin.expect("Somehow there was no down-triangle in timeslice");
         }
-        triangle_vertices
+
+        self.triangles
+            .iter()
+            .enumerate()
+            .map(|(label, triangle)| match triangle.orientation {
+                // bottom-left, top, bottom-right
+                Orientation::Up => [
+                    label,
+                    next_up[self.triangles[prev_down[label]].time],
+                    next_up[label],
+                ],
+                // top-left, top-right, bottom
+                Orientation::Down => [triangle.time, next_up[triangle.time], next_up[label]],
+            })
+            .collect()
     }
 
-    fn vertex_coordinates(&self, origin: usize) -> Vec<Vec<VertexPosition>> {
-        let lengths = self.length_profile(origin);
-        let t_max = lengths.len();
-        let mut vertex_positions: Vec<Vec<VertexPosition>> = Vec::with_capacity(t_max + 1);
-        for t in 0..t_max + 1 {
-            let length = lengths[t % t_max];
-            let shift = 0.0f32; // TODO: make shift such that the total length of the timelike connections is minimized
-            let n = length as f32;
-            vertex_positions.push(
-                (0..length)
-                    .map(|i| {
-                        VertexPosition(
-                            n / TAU * (TAU / n * (i as f32) + shift).cos(),
-                            n / TAU * (TAU / n * (i as f32) + shift).sin(),
-                            t as f32,
-                        )
-                    })
-                    .collect(),
-            )
+    pub fn dual_graph(&self, origin: usize) -> Graph {
+        // Nodes are the triangles, labelled as in the universe, connected to their
+        // right neighbour and (from the down triangles) to their time neighbour
+        let mut nodes: Vec<Option<Node>> = vec![None; self.triangles.len()];
+        for (t, slab) in self.slabs(origin).iter().enumerate() {
+            for (position, &label) in slab.iter().enumerate() {
+                let kind = match self.triangles[label].orientation {
+                    Orientation::Up => "up",
+                    Orientation::Down => "down",
+                };
+                nodes[label] = Some(Node {
+                    kind,
+                    time: t,
+                    position,
+                });
+            }
         }
-        vertex_positions
+        let nodes = nodes
+            .into_iter()
+            .map(|node| node.expect("Somehow a triangle was not part of any slab"))
+            .collect();
+
+        let mut edges = Vec::with_capacity(3 * self.triangles.len() / 2);
+        for (label, triangle) in self.triangles.iter().enumerate() {
+            edges.push(Edge {
+                source: label,
+                target: triangle.right,
+                kind: "right",
+            });
+            if triangle.orientation == Orientation::Down {
+                edges.push(Edge {
+                    source: label,
+                    target: triangle.time,
+                    kind: "time",
+                });
+            }
+        }
+        Graph::new(nodes, edges)
     }
 
-    fn torus_triangle_vertices(&self, origin: usize) -> Vec<(Vertex, Vertex, Vertex)> {
-        // Walk through the slices similarly to find the length profile but now returning triangulation
-        let triangles = &self.triangles;
+    pub fn vertex_graph(&self, origin: usize) -> Graph {
+        // Nodes are the vertices, numbered slice by slice from the origin, connected by
+        // spacelike links (from left to right) and timelike links (from past to future)
+        let mut index = vec![usize::MAX; self.triangles.len()];
+        let mut nodes = Vec::with_capacity(self.triangles.len() / 2);
+        for (t, slab) in self.slabs(origin).iter().enumerate() {
+            let ups = slab
+                .iter()
+                .filter(|&&label| self.triangles[label].orientation == Orientation::Up);
+            for (position, &label) in ups.enumerate() {
+                index[label] = nodes.len();
+                nodes.push(Node {
+                    kind: "vertex",
+                    time: t,
+                    position,
+                });
+            }
+        }
 
-        // Determine the length profile
-        let lengths = self.length_profile(origin);
-        let t_max = lengths.len();
+        // every up triangle has a unique spacelike link at its base,
+        // and every triangle a unique timelike link on its right side
+        let corners = self.triangle_corners();
+        let mut edges = Vec::with_capacity(3 * self.triangles.len() / 2);
+        for (label, triangle) in self.triangles.iter().enumerate() {
+            if triangle.orientation == Orientation::Up {
+                edges.push(Edge {
+                    source: index[corners[label][0]],
+                    target: index[corners[label][2]],
+                    kind: "spacelike",
+                });
+            }
+            edges.push(Edge {
+                source: index[corners[label][2]],
+                target: index[corners[label][1]],
+                kind: "timelike",
+            });
+        }
+        Graph::new(nodes, edges)
+    }
+
+    fn triangle_vertices(&self, origin: usize) -> Vec<(Vertex, Vertex, Vertex)> {
+        // Walk through the slabs similarly to find the length profile but now returning triangulation
+        // the vertex indices are not wrapped around, so the last vertex of slice t has index lengths[t]
+        let slabs = self.slabs(origin);
 
         let mut triangle_vertices: Vec<(Vertex, Vertex, Vertex)> =
-            Vec::with_capacity(self.triangles.len() / 2);
-        let mut marker = origin; // Marks the current triangle
-                                 // Loop over all timeslices
-        for t in 0..t_max {
+            vec![(Vertex(0, 0), Vertex(0, 0), Vertex(0, 0)); self.triangles.len()];
+        for (t, slab) in slabs.iter().enumerate() {
             let mut lower_index: usize = 0;
             let mut upper_index: usize = 0;
-            let mut next_origin: Option<usize> = None;
-            for _ in 0..(lengths[t] + lengths[(t + 1) % t_max]) {
-                // Loop over all triangles
-                match triangles[marker].orientation {
+            for &label in slab {
+                match self.triangles[label].orientation {
                     Orientation::Up => {
-                        let right_index = (lower_index + 1) % lengths[t]; // Determine next index
                         // Add triangle with orientation based on right-handedness
-                        triangle_vertices.push((
+                        triangle_vertices[label] = (
                             Vertex(t, lower_index),
                             Vertex(t + 1, upper_index),
-                            Vertex(t, right_index),
-                        ));
-                        lower_index = right_index // Update index
+                            Vertex(t, lower_index + 1),
+                        );
+                        lower_index += 1;
                     }
                     Orientation::Down => {
-                        if next_origin.is_none() {
-                            next_origin = Some(triangles[marker].time)
-                        }
-                        let right_index = (upper_index + 1) % lengths[(t + 1) % t_max]; // Determine next index
                         // Add triangle with orientation based on right-handedness
-                        triangle_vertices.push((
+                        triangle_vertices[label] = (
                             Vertex(t + 1, upper_index),
-                            Vertex(t + 1, right_index),
+                            Vertex(t + 1, upper_index + 1),
                             Vertex(t, lower_index),
-                        ));
-                        upper_index = right_index // Update index
+                        );
+                        upper_index += 1;
                     }
                 }
-                marker = triangles[marker].right;
             }
-            marker = next_origin.expect("Somehow there was no down-triangle in timeslice");
         }
         triangle_vertices
     }
 
-    fn torus_vertex_coordinates(&self, origin: usize) -> Vec<Vec<VertexPosition>> {
+    /// The number of times every link of the vertex graph winds around space and time,
+    /// in the same order as the edges of `vertex_graph`, such that the winding numbers of a
+    /// closed path add up to its homology class on the torus
+    pub fn edge_windings(&self, origin: usize) -> Vec<(isize, isize)> {
+        // A link winds around space when it crosses from the last vertex of a slice to the
+        // first one, and around time when it crosses from the last slice to the first one
         let lengths = self.length_profile(origin);
         let t_max = lengths.len();
+        let twist = self.twist(&self.slabs(origin));
+        let sheet = |vertex: &Vertex| match vertex.0 == t_max {
+            true => ((vertex.1 + twist) / lengths[0]) as isize,
+            false => (vertex.1 / lengths[vertex.0]) as isize,
+        };
+        let winding = |from: &Vertex, to: &Vertex| {
+            (
+                sheet(to) - sheet(from),
+                (to.0 / t_max) as isize - (from.0 / t_max) as isize,
+            )
+        };
+
+        let vertices = self.triangle_vertices(origin);
+        let mut windings = Vec::with_capacity(3 * self.triangles.len() / 2);
+        for (label, triangle) in self.triangles.iter().enumerate() {
+            let (first, second, third) = &vertices[label];
+            if triangle.orientation == Orientation::Up {
+                windings.push(winding(first, third));
+            }
+            windings.push(winding(third, second));
+        }
+        windings
+    }
+
+    fn vertex_coordinates(&self, origin: usize, embedding: Embedding) -> Vec<Vec<VertexPosition>> {
+        // The positions of the vertices of every slice, including the unwrapped last vertex,
+        // the final slice is the first one again but shifted by the twist of the gluing
+        let lengths = self.length_profile(origin);
+        let t_max = lengths.len();
+        let twist = self.twist(&self.slabs(origin));
+        let mean_length = (self.triangles.len() / 2) as f32 / t_max as f32;
+
         let mut vertex_positions: Vec<Vec<VertexPosition>> = Vec::with_capacity(t_max + 1);
         for t in 0..t_max + 1 {
             let length = lengths[t % t_max];
+            let offset = if t == t_max { twist } else { 0 };
             let shift = 0.0f32; // TODO: make shift such that the total length of the timelike connections is minimized
-            let rinner = (length as f32) / TAU;
-            let router = (t_max as f32) / TAU;
             vertex_positions.push(
-                (0..length)
+                (offset..=length + offset)
                     .map(|i| {
-                        VertexPosition(
-                            (router + rinner*((i as f32 + shift)/rinner).cos()) * ((t as f32 + shift)/router).cos(),
-                            (router + rinner*((i as f32 + shift)/rinner).cos()) * ((t as f32 + shift)/router).sin(),
-                            rinner*((i as f32 + shift)/rinner).sin(),
+                        embedding.position(
+                            t as f32,
+                            i as f32 + shift,
+                            length as f32,
+                            t_max as f32,
+                            mean_length,
                         )
                     })
                     .collect(),
@@ -407,10 +1081,13 @@ impl Universe {
         vertex_positions
     }
 
-    pub fn triangle_coordinates(&self) -> Vec<(VertexPosition, VertexPosition, VertexPosition)> {
-        let origin = 0;
+    pub fn triangle_coordinates(
+        &self,
+        origin: usize,
+        embedding: Embedding,
+    ) -> Vec<(VertexPosition, VertexPosition, VertexPosition)> {
         let vertices = self.triangle_vertices(origin);
-        let vertex_coordinates = self.vertex_coordinates(origin);
+        let vertex_coordinates = self.vertex_coordinates(origin, embedding);
         vertices
             .iter()
             .map(|triangle| {
@@ -422,40 +1099,260 @@ impl Universe {
             })
             .collect()
     }
+}
 
-    pub fn torus_triangle_coordinates(&self) -> Vec<(VertexPosition, VertexPosition, VertexPosition)> {
-        let origin = 0;
-        let vertices = self.torus_triangle_vertices(origin);
-        let vertex_coordinates = self.torus_vertex_coordinates(origin);
-        vertices
-            .iter()
-            .map(|triangle| {
-                (
-                    vertex_coordinates[triangle.0 .0][triangle.0 .1],
-                    vertex_coordinates[triangle.1 .0][triangle.1 .1],
-                    vertex_coordinates[triangle.2 .0][triangle.2 .1],
+impl LabelSet {
+    fn new(capacity: usize) -> Self {
+        LabelSet {
+            labels: Vec::new(),
+            positions: vec![None; capacity],
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn insert(&mut self, label: usize) {
+        if self.positions[label].is_none() {
+            self.positions[label] = Some(self.labels.len());
+            self.labels.push(label);
+        }
+    }
+
+    fn remove(&mut self, label: usize) {
+        // fill the gap with the last label
+        if let Some(position) = self.positions[label].take() {
+            let last = self
+                .labels
+                .pop()
+                .expect("Somehow a label is in an empty set");
+            if last != label {
+                self.labels[position] = last;
+                self.positions[last] = Some(position);
+            }
+        }
+    }
+
+    fn sample(&self) -> usize {
+        self.labels[fastrand::usize(..self.labels.len())]
+    }
+}
+
+impl Embedding {
+    fn position(
+        &self,
+        t: f32,
+        i: f32,
+        length: f32,
+        t_max: f32,
+        mean_length: f32,
+    ) -> VertexPosition {
+        // Embed vertex i of a slice with the given length at time t
+        match self {
+            Embedding::Torus => {
+                let rinner = length / TAU;
+                let router = t_max / TAU;
+                VertexPosition(
+                    (router + rinner * (i / rinner).cos()) * (t / router).cos(),
+                    (router + rinner * (i / rinner).cos()) * (t / router).sin(),
+                    rinner * (i / rinner).sin(),
                 )
-            })
-            .collect()
+            }
+            Embedding::Cylinder => VertexPosition(
+                length / TAU * (TAU / length * i).cos(),
+                length / TAU * (TAU / length * i).sin(),
+                t,
+            ),
+            Embedding::Strip => VertexPosition(mean_length / length * i, t, 0.0),
+        }
+    }
+}
+
+impl FromStr for Embedding {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "torus" => Ok(Embedding::Torus),
+            "cylinder" => Ok(Embedding::Cylinder),
+            "strip" => Ok(Embedding::Strip),
+            _ => Err(format!(
+                "unknown embedding '{}', expected 'torus', 'cylinder' or 'strip'",
+                s
+            )),
+        }
+    }
+}
+
+impl FromStr for Start {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(Start::Flat),
+            "random" => Ok(Start::Random),
+            "pinched" => Ok(Start::Pinched),
+            _ => Err(format!(
+                "unknown start '{}', expected 'flat', 'random' or 'pinched'",
+                s
+            )),
+        }
+    }
+}
+
+impl FromStr for LengthDistribution {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "multinomial" => Ok(LengthDistribution::Multinomial),
+            "uniform" => Ok(LengthDistribution::Uniform),
+            _ => Err(format!(
+                "unknown length distribution '{}', expected 'multinomial' or 'uniform'",
+                s
+            )),
+        }
+    }
+}
+
+impl Statistic {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Statistic::Mean => "mean",
+            Statistic::Variance => "variance",
+            Statistic::Stdev => "stdev",
+            Statistic::Skewness => "skewness",
+            Statistic::Kurtosis => "kurtosis",
+            Statistic::Min => "min",
+            Statistic::Max => "max",
+            Statistic::Binder => "binder",
+        }
+    }
+}
+
+impl FromStr for Statistic {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mean" => Ok(Statistic::Mean),
+            "variance" => Ok(Statistic::Variance),
+            "stdev" => Ok(Statistic::Stdev),
+            "skewness" => Ok(Statistic::Skewness),
+            "kurtosis" => Ok(Statistic::Kurtosis),
+            "min" => Ok(Statistic::Min),
+            "max" => Ok(Statistic::Max),
+            "binder" => Ok(Statistic::Binder),
+            _ => Err(format!("unknown statistic '{}'", s)),
+        }
     }
 }
 
 impl LengthProfile {
-    pub fn stdev(&self) -> f32 {
-        let lengths = &self.0;
-        let n = lengths.len();
-        let mean = lengths.iter().sum::<usize>() / n;
-        (lengths
+    pub fn mean(&self) -> f64 {
+        self.0.iter().sum::<usize>() as f64 / self.0.len() as f64
+    }
+
+    pub fn variance(&self) -> f64 {
+        self.moments().variance
+    }
+
+    pub fn stdev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    /// The skewness m3 / m2^(3/2) of the slice lengths, or zero if all slices have the same length
+    pub fn skewness(&self) -> f64 {
+        self.moments().skewness()
+    }
+
+    /// The kurtosis m4 / m2^2 of the slice lengths, which is 3 for a normal distribution, and
+    /// taken to be 3 as well if all slices have the same length
+    pub fn kurtosis(&self) -> f64 {
+        self.moments().kurtosis()
+    }
+
+    pub fn min(&self) -> usize {
+        *self
+            .0
             .iter()
-            .map(|&x| (x - mean) * (x - mean))
-            .sum::<usize>() as f32
-            / (n as f32))
-            .sqrt()
+            .min()
+            .expect("Somehow the length profile is empty")
     }
 
-    fn len(&self) -> usize {
+    pub fn max(&self) -> usize {
+        *self
+            .0
+            .iter()
+            .max()
+            .expect("Somehow the length profile is empty")
+    }
+
+    /// The Binder cumulant 1 - m4 / (3 m2^2) of the slice lengths, vanishing for a normal distribution
+    /// and if all slices have the same length
+    pub fn binder_cumulant(&self) -> f64 {
+        self.moments().binder_cumulant()
+    }
+
+    pub fn statistic(&self, statistic: Statistic) -> f64 {
+        match statistic {
+            Statistic::Mean => self.mean(),
+            Statistic::Variance => self.variance(),
+            Statistic::Stdev => self.stdev(),
+            Statistic::Skewness => self.skewness(),
+            Statistic::Kurtosis => self.kurtosis(),
+            Statistic::Min => self.min() as f64,
+            Statistic::Max => self.max() as f64,
+            Statistic::Binder => self.binder_cumulant(),
+        }
+    }
+
+    pub fn moments(&self) -> Moments {
+        Moments::from_weighted(self.0.iter().map(|&l| (l as f64, 1.0)))
+    }
+
+    pub fn lengths(&self) -> &[usize] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The slice closest to the centre of volume, taking the periodicity in time into account
+    /// by using the phase of the lowest Fourier mode of the profile
+    pub fn centre_of_volume(&self) -> usize {
+        let t_max = self.0.len();
+        let (cos, sin) = self
+            .0
+            .iter()
+            .enumerate()
+            .map(|(t, &l)| {
+                let angle = std::f64::consts::TAU * t as f64 / t_max as f64;
+                (l as f64 * angle.cos(), l as f64 * angle.sin())
+            })
+            .fold((0.0, 0.0), |(c, s), (x, y)| (c + x, s + y));
+        let phase = sin.atan2(cos).rem_euclid(std::f64::consts::TAU);
+        (phase / std::f64::consts::TAU * t_max as f64).round() as usize % t_max
+    }
+
+    /// The first slice with the largest length
+    pub fn maximum(&self) -> usize {
+        let largest = self
+            .0
+            .iter()
+            .max()
+            .expect("Somehow the length profile is empty");
+        self.0.iter().position(|l| l == largest).unwrap()
+    }
+
+    /// The same profile, but starting from the given slice
+    pub fn rotated(&self, start: usize) -> LengthProfile {
+        let mut lengths = self.0.clone();
+        lengths.rotate_left(start % self.0.len());
+        LengthProfile(lengths)
+    }
 }
 
 impl Index<usize> for LengthProfile {
@@ -479,3 +1376,80 @@ impl fmt::Display for VertexPosition {
         write!(f, "{},{},{}", self.0, self.1, self.2)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::{MoveKind, MoveWeight};
+
+    fn assert_consistent(universe: &Universe) {
+        assert_eq!(universe.validate(), Ok(()));
+        let labels = 0..universe.size();
+
+        let order_four: HashSet<usize> = labels
+            .clone()
+            .filter(|&label| universe.is_order_four_at(label))
+            .collect();
+        assert_eq!(universe.order_four, order_four);
+
+        let flippable: HashSet<usize> = labels
+            .filter(|&label| universe.is_flippable(label))
+            .collect();
+        let set = &universe.flippable;
+        assert_eq!(set.len(), flippable.len());
+        assert_eq!(
+            set.labels.iter().copied().collect::<HashSet<_>>(),
+            flippable
+        );
+        for (position, &label) in set.labels.iter().enumerate() {
+            assert_eq!(set.positions[label], Some(position));
+        }
+        let positioned = set.positions.iter().filter(|position| position.is_some());
+        assert_eq!(positioned.count(), flippable.len());
+    }
+
+    #[test]
+    fn moves_keep_bookkeeping() {
+        // the order four vertices and flippable pairs are updated by every move, which should
+        // agree with recomputing them, both for ordinary and rejection-free steps
+        let kinds = [MoveKind::Shard, MoveKind::Heatbath, MoveKind::Cluster];
+        for timespan in 2..=8 {
+            for weights in kinds
+                .iter()
+                .map(|&kind| vec![MoveWeight(MoveKind::Flip, 1.0), MoveWeight(kind, 0.5)])
+                .chain(std::iter::once(
+                    std::iter::once(MoveKind::Flip)
+                        .chain(kinds)
+                        .map(|kind| MoveWeight(kind, 1.0))
+                        .collect(),
+                ))
+            {
+                let mut universe = Universe::random(timespan, 4, LengthDistribution::Multinomial);
+                let mut moves = Moves::new(&weights);
+                assert_consistent(&universe);
+                for step in 0..20000 {
+                    match step % 2 {
+                        0 => universe.mcmc_step(&mut moves),
+                        _ => {
+                            universe.rejection_free_step(&mut moves, 10);
+                        }
+                    }
+                    if step % 10 == 0 {
+                        assert_consistent(&universe);
+                    }
+                }
+                assert_consistent(&universe);
+            }
+        }
+    }
+
+    #[test]
+    fn pinched_start() {
+        // a quadratic construction would take minutes at this size
+        let universe = Universe::pinched(1000, 100);
+        assert!(universe.validate().is_ok());
+        let lengths = universe.length_profile(0).lengths().to_vec();
+        assert_eq!(lengths.iter().sum::<usize>(), 1000 * 100);
+        assert_eq!(lengths.iter().filter(|&&length| length == 1).count(), 999);
+    }
+}